@@ -0,0 +1,312 @@
+//! Saved stories.
+//!
+//! Bookmarks live in `bookmarks.json` under the XDG data directory
+//! (`~/.local/share/hn` on Linux). Each one keeps a full snapshot of the item, so
+//! the Saved view works without the network and survives the item disappearing
+//! from every feed.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::Item;
+
+/// A saved story
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bookmark {
+    /// The item as it was when saved
+    pub item: Item,
+    /// Unix timestamp of when it was saved
+    pub saved_at: u64,
+    /// Free-form labels, without the leading `#`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Free-form note
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// The bookmark store, newest first
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    /// File the bookmarks are written to, or `None` to keep them in memory only
+    path: Option<PathBuf>,
+    entries: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Default location of the bookmarks file
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("hn").join("bookmarks.json"))
+    }
+
+    /// Opens the bookmarks file at its default location. A missing file yields an
+    /// empty store, while a corrupt one is an error so it is never overwritten.
+    pub fn open() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) => Self::load(path),
+            None => Ok(Self::in_memory()),
+        }
+    }
+
+    /// Opens the bookmarks file at `path`
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let entries = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Invalid bookmarks file {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Cannot read bookmarks {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            entries,
+        })
+    }
+
+    /// A store that is never written to disk
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// All bookmarks, newest first
+    pub fn entries(&self) -> &[Bookmark] {
+        &self.entries
+    }
+
+    /// Whether the item with this id is saved
+    pub fn contains(&self, id: u64) -> bool {
+        self.entries.iter().any(|entry| entry.item.id == id)
+    }
+
+    /// Saves the item, or removes it if it is already saved.
+    ///
+    /// Returns whether the item is saved now. The change is kept in memory even if
+    /// writing the file fails.
+    pub fn toggle(&mut self, item: &Item) -> Result<bool> {
+        let saved = if self.contains(item.id) {
+            self.entries.retain(|entry| entry.item.id != item.id);
+            false
+        } else {
+            let bookmark = Bookmark {
+                item: item.clone(),
+                saved_at: now(),
+                tags: Vec::new(),
+                note: None,
+            };
+            self.entries.insert(0, bookmark);
+            true
+        };
+        self.save()?;
+        Ok(saved)
+    }
+
+    /// Replaces the tags of a saved item
+    pub fn set_tags(&mut self, id: u64, tags: Vec<String>) -> Result<()> {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.item.id == id) {
+            entry.tags = tags;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the note of a saved item
+    pub fn set_note(&mut self, id: u64, note: Option<String>) -> Result<()> {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.item.id == id) {
+            entry.note = note;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Indexes of the bookmarks matching a filter, newest first.
+    ///
+    /// Every word of the filter must occur, case-insensitively, in the title,
+    /// domain, author, note or tags; `#word` only matches tags.
+    pub fn matching(&self, filter: &str) -> Vec<usize> {
+        let words: Vec<String> = filter.split_whitespace().map(str::to_lowercase).collect();
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| words.iter().all(|word| entry.matches(word)))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Writes all bookmarks, through a temporary file so a crash never leaves a
+    /// truncated file behind
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        write(path, &self.entries)
+            .with_context(|| format!("Cannot save bookmarks to {}", path.display()))
+    }
+}
+
+impl Bookmark {
+    /// Whether one lowercase filter word matches this bookmark
+    fn matches(&self, word: &str) -> bool {
+        let tagged = |tag: &str| self.tags.iter().any(|t| t.to_lowercase().contains(tag));
+        if let Some(tag) = word.strip_prefix('#') {
+            return tagged(tag);
+        }
+        let item = &self.item;
+        let fields = [
+            Some(item.display_title()),
+            item.domain().map(str::to_string),
+            item.by.clone(),
+            self.note.clone(),
+        ];
+        fields
+            .into_iter()
+            .flatten()
+            .any(|field| field.to_lowercase().contains(word))
+            || tagged(word)
+    }
+}
+
+/// Serializes bookmarks to `path`, creating its directory if needed
+fn write(path: &Path, entries: &[Bookmark]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let data = serde_json::to_vec_pretty(entries)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Current Unix time in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: u64, title: &str, by: &str, url: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "type": "story", "title": title, "by": by, "url": url, "time": 0
+        }))
+        .unwrap()
+    }
+
+    /// A bookmarks file path in a fresh temporary directory
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("hn-bookmarks-test-{}-{}", std::process::id(), name))
+            .join("bookmarks.json")
+    }
+
+    fn bookmarks() -> Bookmarks {
+        let mut bookmarks = Bookmarks::in_memory();
+        let items = [
+            item(
+                1,
+                "Async Rust in practice",
+                "alice",
+                "https://blog.example.com/a",
+            ),
+            item(2, "Gardening tips", "bob", "https://garden.example.org/b"),
+            item(3, "Why Go", "carol", "https://rustacean.example.net/c"),
+        ];
+        for item in &items {
+            bookmarks.toggle(item).unwrap();
+        }
+        bookmarks.set_tags(2, vec!["Rust".to_string()]).unwrap();
+        bookmarks
+            .set_tags(3, vec!["languages".to_string()])
+            .unwrap();
+        bookmarks
+            .set_note(1, Some("Read the part on cancellation".to_string()))
+            .unwrap();
+        bookmarks
+    }
+
+    fn ids(bookmarks: &Bookmarks, filter: &str) -> Vec<u64> {
+        bookmarks
+            .matching(filter)
+            .into_iter()
+            .map(|index| bookmarks.entries()[index].item.id)
+            .collect()
+    }
+
+    #[test]
+    fn tags_match_only_tags() {
+        let bookmarks = bookmarks();
+        assert_eq!(ids(&bookmarks, "#rust"), vec![2]);
+        assert_eq!(ids(&bookmarks, "#RUST"), vec![2]);
+        assert_eq!(ids(&bookmarks, "#lang"), vec![3]);
+        assert!(ids(&bookmarks, "#gardening").is_empty());
+    }
+
+    #[test]
+    fn free_text_matches_every_field() {
+        let bookmarks = bookmarks();
+        // Title, tag and domain, newest first
+        assert_eq!(ids(&bookmarks, "rust"), vec![3, 2, 1]);
+        assert_eq!(ids(&bookmarks, "carol"), vec![3]);
+        assert_eq!(ids(&bookmarks, "cancellation"), vec![1]);
+        assert_eq!(ids(&bookmarks, "garden.example"), vec![2]);
+        // Every word must match
+        assert_eq!(ids(&bookmarks, "rust #languages"), vec![3]);
+        assert_eq!(ids(&bookmarks, "async   PRACTICE"), vec![1]);
+        assert!(ids(&bookmarks, "rust python").is_empty());
+        assert_eq!(ids(&bookmarks, ""), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn tags_and_notes_survive_a_reload() {
+        let path = temporary_path("round-trip");
+        let _ = std::fs::remove_file(&path);
+        let mut bookmarks = Bookmarks::load(path.clone()).unwrap();
+        assert!(bookmarks.entries().is_empty());
+
+        for id in [1, 2, 3] {
+            bookmarks.toggle(&item(id, "Story", "pg", "")).unwrap();
+        }
+        bookmarks
+            .set_tags(1, vec!["rust".to_string(), "later".to_string()])
+            .unwrap();
+        bookmarks.set_note(1, Some("A note".to_string())).unwrap();
+        assert!(!bookmarks.toggle(&item(2, "Story", "pg", "")).unwrap());
+
+        let reloaded = Bookmarks::load(path.clone()).unwrap();
+        let saved: Vec<_> = reloaded
+            .entries()
+            .iter()
+            .map(|entry| (entry.item.id, entry.tags.clone(), entry.note.clone()))
+            .collect();
+        assert_eq!(
+            saved,
+            vec![
+                (3, Vec::new(), None),
+                (
+                    1,
+                    vec!["rust".to_string(), "later".to_string()],
+                    Some("A note".to_string())
+                ),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error() {
+        let path = temporary_path("corrupt");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "[{\"item\":").unwrap();
+        assert!(Bookmarks::load(path.clone()).is_err());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
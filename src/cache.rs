@@ -0,0 +1,306 @@
+//! Persistent on-disk cache of fetched items, feed id lists and user profiles.
+//!
+//! Entries live under the XDG cache directory (`~/.cache/hn` on Linux) as one
+//! JSON file per item, feed or user, each stamped with the time it was fetched.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{Feed, Item, User};
+
+/// How long a feed's id list is served from the cache before refetching (seconds)
+const FEED_TTL: u64 = 5 * 60;
+
+/// How long a recent item is served from the cache before refetching (seconds)
+const ITEM_TTL: u64 = 5 * 60;
+
+/// Items older than this no longer collect votes or comments (seconds)
+const SETTLED_AGE: u64 = 14 * 24 * 3600;
+
+/// How long a settled item is served from the cache before refetching (seconds)
+const SETTLED_ITEM_TTL: u64 = 7 * 24 * 3600;
+
+/// How long a user profile is served from the cache before refetching (seconds)
+const USER_TTL: u64 = 60 * 60;
+
+/// Numbers the temporary files of writes, so concurrent writes of one entry never
+/// share a temporary file
+static WRITE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A cached value together with the time it was fetched
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    /// Unix timestamp of the fetch
+    fetched_at: u64,
+    /// The cached value
+    value: T,
+}
+
+/// Handle to the on-disk cache
+#[derive(Debug, Clone)]
+pub struct Cache {
+    /// Root cache directory, or `None` if caching is unavailable
+    dir: Option<PathBuf>,
+    /// Whether the network must not be used at all
+    pub offline: bool,
+}
+
+/// Whether a cached value may be used without asking the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// Within its freshness window
+    Fresh,
+    /// Past its freshness window, only good as a fallback
+    Stale,
+}
+
+impl Cache {
+    /// Opens the cache in the user's XDG cache directory
+    pub fn open(offline: bool) -> Self {
+        Self {
+            dir: dirs::cache_dir().map(|dir| dir.join("hn")),
+            offline,
+        }
+    }
+
+    /// A cache that never stores or returns anything
+    pub fn disabled() -> Self {
+        Self {
+            dir: None,
+            offline: false,
+        }
+    }
+
+    /// A cache in a fresh temporary directory, for tests
+    #[cfg(test)]
+    pub fn temporary(offline: bool) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "hn-cache-test-{}-{}",
+            std::process::id(),
+            WRITE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        Self {
+            dir: Some(dir),
+            offline,
+        }
+    }
+
+    /// Looks up a cached item
+    pub async fn item(&self, id: u64) -> Option<(Item, Freshness)> {
+        let entry: Entry<Item> = self.read(&self.path("items", &id.to_string())?).await?;
+        let age = now().saturating_sub(entry.value.time);
+        let ttl = if age > SETTLED_AGE {
+            SETTLED_ITEM_TTL
+        } else {
+            ITEM_TTL
+        };
+        let freshness = freshness(entry.fetched_at, ttl);
+        Some((entry.value, freshness))
+    }
+
+    /// Stores a freshly fetched item
+    pub async fn store_item(&self, item: &Item) {
+        if let Some(path) = self.path("items", &item.id.to_string()) {
+            self.write(&path, item).await;
+        }
+    }
+
+    /// Looks up a feed's cached id list
+    pub async fn feed_ids(&self, feed: Feed) -> Option<(Vec<u64>, Freshness)> {
+        let entry: Entry<Vec<u64>> = self.read(&self.path("feeds", feed.endpoint())?).await?;
+        let freshness = freshness(entry.fetched_at, FEED_TTL);
+        Some((entry.value, freshness))
+    }
+
+    /// Stores a freshly fetched feed id list
+    pub async fn store_feed_ids(&self, feed: Feed, ids: &[u64]) {
+        if let Some(path) = self.path("feeds", feed.endpoint()) {
+            self.write(&path, ids).await;
+        }
+    }
+
+    /// Looks up a cached user profile
+    pub async fn user(&self, id: &str) -> Option<(User, Freshness)> {
+        let entry: Entry<User> = self.read(&self.path("users", id)?).await?;
+        let freshness = freshness(entry.fetched_at, USER_TTL);
+        Some((entry.value, freshness))
+    }
+
+    /// Stores a freshly fetched user profile
+    pub async fn store_user(&self, user: &User) {
+        if let Some(path) = self.path("users", &user.id) {
+            self.write(&path, user).await;
+        }
+    }
+
+    /// Path of the file for `key` in the `kind` subdirectory
+    fn path(&self, kind: &str, key: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(kind).join(format!("{}.json", key)))
+    }
+
+    /// Reads an entry, treating unreadable or corrupt files as missing
+    async fn read<T: DeserializeOwned>(&self, path: &Path) -> Option<Entry<T>> {
+        let data = tokio::fs::read(path).await.ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    /// Writes an entry stamped with the current time.
+    ///
+    /// Failures are ignored: the cache is an optimization, not a requirement.
+    async fn write<T: Serialize + ?Sized>(&self, path: &Path, value: &T) {
+        let entry = Entry {
+            fetched_at: now(),
+            value,
+        };
+        let Ok(data) = serde_json::to_vec(&entry) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+        // Write to a temporary file of its own first so readers never see a
+        // partial entry, even while another write of the same entry runs
+        let tmp = path.with_extension(format!(
+            "json.{}.{}.tmp",
+            std::process::id(),
+            WRITE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if tokio::fs::write(&tmp, data).await.is_err()
+            || tokio::fs::rename(&tmp, path).await.is_err()
+        {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+    }
+}
+
+/// Current Unix time in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Classifies an entry fetched at `fetched_at` against a time-to-live
+fn freshness(fetched_at: u64, ttl: u64) -> Freshness {
+    if now().saturating_sub(fetched_at) < ttl {
+        Freshness::Fresh
+    } else {
+        Freshness::Stale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+
+    use super::*;
+
+    fn item(id: u64, time: u64, title: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "type": "story", "title": title, "time": time
+        }))
+        .unwrap()
+    }
+
+    /// Stores `value` at `path` as if it had been fetched `age` seconds ago
+    fn backdate<T: Serialize>(path: &Path, value: T, age: u64) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let entry = Entry {
+            fetched_at: now() - age,
+            value,
+        };
+        std::fs::write(path, serde_json::to_vec(&entry).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stored_entries_are_fresh() {
+        let cache = Cache::temporary(false);
+        cache.store_item(&item(1, now(), "One")).await;
+        cache.store_feed_ids(Feed::Top, &[1, 2]).await;
+
+        let (cached, freshness) = cache.item(1).await.unwrap();
+        assert_eq!(
+            (cached.title.as_deref(), freshness),
+            (Some("One"), Freshness::Fresh)
+        );
+        assert_eq!(
+            cache.feed_ids(Feed::Top).await,
+            Some((vec![1, 2], Freshness::Fresh))
+        );
+        assert!(cache.item(2).await.is_none());
+        assert!(cache.feed_ids(Feed::New).await.is_none());
+        assert!(cache.user("pg").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn entries_go_stale_after_their_ttl() {
+        let cache = Cache::temporary(false);
+        backdate(
+            &cache.path("feeds", "topstories").unwrap(),
+            [1],
+            FEED_TTL - 10,
+        );
+        backdate(
+            &cache.path("feeds", "newstories").unwrap(),
+            [1],
+            FEED_TTL + 1,
+        );
+        assert_eq!(cache.feed_ids(Feed::Top).await.unwrap().1, Freshness::Fresh);
+        assert_eq!(cache.feed_ids(Feed::New).await.unwrap().1, Freshness::Stale);
+
+        // Recent items go stale quickly, settled ones are kept for a week
+        let recent = item(1, now(), "Recent");
+        let settled = item(2, now() - SETTLED_AGE - 1, "Settled");
+        backdate(&cache.path("items", "1").unwrap(), &recent, ITEM_TTL + 1);
+        backdate(&cache.path("items", "2").unwrap(), &settled, ITEM_TTL + 1);
+        assert_eq!(cache.item(1).await.unwrap().1, Freshness::Stale);
+        assert_eq!(cache.item(2).await.unwrap().1, Freshness::Fresh);
+        backdate(
+            &cache.path("items", "2").unwrap(),
+            &settled,
+            SETTLED_ITEM_TTL + 1,
+        );
+        assert_eq!(cache.item(2).await.unwrap().1, Freshness::Stale);
+    }
+
+    #[tokio::test]
+    async fn corrupt_entries_are_missing() {
+        let cache = Cache::temporary(false);
+        let path = cache.path("items", "1").unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"{\"fetched_at\": 1, \"val").unwrap();
+        assert!(cache.item(1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes_of_one_entry_do_not_collide() {
+        let cache = Cache::temporary(false);
+        let items: Vec<Item> = (0..20).map(|n| item(1, now(), &n.to_string())).collect();
+        join_all(items.iter().map(|item| cache.store_item(item))).await;
+
+        // One of the writes wins whole, and no temporary file is left behind
+        let (cached, _) = cache.item(1).await.unwrap();
+        assert!(cached.title.unwrap().parse::<u32>().unwrap() < 20);
+        let dir = cache
+            .path("items", "1")
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn disabled_cache_stores_nothing() {
+        let cache = Cache::disabled();
+        cache.store_item(&item(1, now(), "One")).await;
+        assert!(cache.item(1).await.is_none());
+    }
+}
@@ -0,0 +1,581 @@
+//! Sources of Hacker News data.
+//!
+//! The app talks to Hacker News through the [`HnClient`] trait. [`HttpClient`]
+//! speaks the official Firebase API (or any mirror of it) and the Algolia search
+//! API, while [`FixtureClient`] serves a fixed in-memory data set so the whole TUI
+//! can run without a network. [`CoalescingClient`] wraps either one so that
+//! overlapping requests for the same item are sent once.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+
+use crate::live::{self, LiveEvent};
+use crate::search::{self, SearchKind, SearchPage, SearchQuery};
+use crate::{Feed, Item, ItemType, User};
+
+/// Default base URL of the Hacker News Firebase API
+pub const DEFAULT_API_URL: &str = "https://hacker-news.firebaseio.com/v0";
+
+/// A live stream that stays silent this long is considered dead. Firebase sends a
+/// keep-alive event every 30 seconds.
+const STREAM_READ_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Source of Hacker News items, feeds, users and search results
+#[async_trait]
+pub trait HnClient: Send + Sync + Debug {
+    /// Fetches the ranked id list of a feed
+    async fn feed_ids(&self, feed: Feed) -> Result<Vec<u64>>;
+
+    /// Fetches a single item
+    async fn item(&self, id: u64) -> Result<Item>;
+
+    /// Fetches a single item with a request of its own, never one that started
+    /// earlier, as a refresh must not be answered with older data
+    async fn fresh_item(&self, id: u64) -> Result<Item> {
+        self.item(id).await
+    }
+
+    /// Fetches a user profile
+    async fn user(&self, name: &str) -> Result<User>;
+
+    /// Runs a full-text search and returns one page of results
+    async fn search(
+        &self,
+        query: &SearchQuery,
+        page: usize,
+        hits_per_page: usize,
+    ) -> Result<SearchPage>;
+
+    /// Downloads the HTML of a story's web page
+    async fn article(&self, url: &str) -> Result<String>;
+
+    /// Streams live changes to a feed and to recently updated items into `on_event`.
+    ///
+    /// Sends [`LiveEvent::Streaming`] once connected and returns when the stream ends.
+    /// Sources that cannot stream fail right away, and callers poll instead.
+    async fn watch(&self, feed: Feed, on_event: &(dyn Fn(LiveEvent) + Send + Sync)) -> Result<()> {
+        let _ = (feed, on_event);
+        bail!("live updates are not supported by this source")
+    }
+}
+
+// ===== HTTP CLIENT =====
+
+/// Client for the Firebase API and the Algolia search API over HTTP
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    /// Shared HTTP connection pool
+    http: Client,
+    /// Connection pool for event streams, which must not time out as a whole
+    stream_http: Client,
+    /// Base URL of the Firebase API, without a trailing slash
+    api_url: String,
+    /// Base URL of the search API, without a trailing slash
+    search_url: String,
+}
+
+impl HttpClient {
+    /// Creates a client for the given API base URLs
+    pub fn new(api_url: &str, search_url: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            http: Client::builder()
+                .timeout(timeout)
+                .user_agent(concat!("hn-tui/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+            stream_http: Client::builder()
+                .connect_timeout(timeout)
+                .read_timeout(STREAM_READ_TIMEOUT)
+                .build()?,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            search_url: search_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Fetches `<api_url>/<path>.json`, where `null` means the resource does not exist
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Option<T>> {
+        let url = format!("{}/{}.json", self.api_url, path);
+        let response = self.http.get(&url).send().await?.error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    /// Opens the event stream of `<api_url>/<path>.json`
+    async fn open_stream(&self, path: &str) -> Result<Response> {
+        let url = format!("{}/{}.json", self.api_url, path);
+        let response = self
+            .stream_http
+            .get(&url)
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+        let is_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if !is_stream {
+            bail!("{} does not support streaming", url);
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl HnClient for HttpClient {
+    async fn feed_ids(&self, feed: Feed) -> Result<Vec<u64>> {
+        Ok(self.get(feed.endpoint()).await?.unwrap_or_default())
+    }
+
+    async fn item(&self, id: u64) -> Result<Item> {
+        self.get(&format!("item/{}", id))
+            .await?
+            .ok_or_else(|| anyhow!("item {} does not exist", id))
+    }
+
+    async fn user(&self, name: &str) -> Result<User> {
+        self.get(&format!("user/{}", name))
+            .await?
+            .ok_or_else(|| anyhow!("No user named {}", name))
+    }
+
+    async fn search(
+        &self,
+        query: &SearchQuery,
+        page: usize,
+        hits_per_page: usize,
+    ) -> Result<SearchPage> {
+        search::search(&self.http, &self.search_url, query, page, hits_per_page).await
+    }
+
+    async fn article(&self, url: &str) -> Result<String> {
+        let response = self
+            .http
+            .get(url)
+            .header(ACCEPT, "text/html,application/xhtml+xml")
+            .send()
+            .await?
+            .error_for_status()?;
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("text/html")
+            .to_string();
+        if !content_type.contains("html") {
+            bail!(
+                "Not a web page ({}), press o to open it in a browser",
+                content_type
+            );
+        }
+        Ok(response.text().await?)
+    }
+
+    async fn watch(&self, feed: Feed, on_event: &(dyn Fn(LiveEvent) + Send + Sync)) -> Result<()> {
+        // Rank changes come from the feed itself, score and comment changes from the
+        // list of recently updated items
+        let ids = self.open_stream(feed.endpoint()).await?;
+        let updates = self.open_stream("updates").await?;
+        on_event(LiveEvent::Streaming);
+
+        tokio::try_join!(
+            live::read_stream(ids, |value| on_event(LiveEvent::FeedIds(live::ids(value)))),
+            live::read_stream(updates, |value| {
+                on_event(LiveEvent::ItemsChanged(live::ids(&value["items"])))
+            }),
+        )?;
+        Ok(())
+    }
+}
+
+// ===== FIXTURE CLIENT =====
+
+/// On-disk layout of a fixture file
+#[derive(Deserialize, Debug, Default)]
+struct Fixture {
+    /// Feed id lists keyed by endpoint name ("topstories", "askstories", ...)
+    #[serde(default)]
+    feeds: HashMap<String, Vec<u64>>,
+    /// Every item the fixture knows about
+    #[serde(default)]
+    items: Vec<Item>,
+    /// Every user the fixture knows about
+    #[serde(default)]
+    users: Vec<User>,
+    /// HTML of story web pages keyed by URL
+    #[serde(default)]
+    pages: HashMap<String, String>,
+}
+
+/// In-memory client serving a fixed data set
+#[derive(Debug, Default)]
+pub struct FixtureClient {
+    feeds: HashMap<String, Vec<u64>>,
+    items: HashMap<u64, Item>,
+    users: HashMap<String, User>,
+    pages: HashMap<String, String>,
+}
+
+impl FixtureClient {
+    /// Builds a client from fixture JSON:
+    /// `{"feeds": {"topstories": [1]}, "items": [{"id": 1, ...}], "users": [...], "pages": {"https://...": "<html>..."}}`
+    pub fn from_json(json: &str) -> Result<Self> {
+        let fixture: Fixture = serde_json::from_str(json)?;
+        Ok(Self {
+            feeds: fixture.feeds,
+            items: fixture
+                .items
+                .into_iter()
+                .map(|item| (item.id, item))
+                .collect(),
+            users: fixture
+                .users
+                .into_iter()
+                .map(|user| (user.id.clone(), user))
+                .collect(),
+            pages: fixture.pages,
+        })
+    }
+
+    /// Loads a fixture file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read fixture {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("Invalid fixture {}", path.display()))
+    }
+
+    /// Whether an item satisfies the filters of a search query
+    fn matches(item: &Item, query: &SearchQuery) -> bool {
+        let kind_matches = match query.kind {
+            SearchKind::Stories => item.kind == ItemType::Story,
+            SearchKind::Comments => item.kind == ItemType::Comment,
+            SearchKind::All => item.kind != ItemType::PollOpt,
+        };
+        let text = query.text.to_lowercase();
+        let text_matches = text.is_empty()
+            || [&item.title, &item.text, &item.url]
+                .into_iter()
+                .flatten()
+                .any(|field| field.to_lowercase().contains(&text));
+
+        kind_matches
+            && text_matches
+            && !item.deleted
+            && query
+                .min_points
+                .is_none_or(|min| item.score.unwrap_or(0) >= min)
+            && query.after.is_none_or(|after| item.time >= after)
+            && query.before.is_none_or(|before| item.time < before)
+    }
+}
+
+#[async_trait]
+impl HnClient for FixtureClient {
+    async fn feed_ids(&self, feed: Feed) -> Result<Vec<u64>> {
+        Ok(self.feeds.get(feed.endpoint()).cloned().unwrap_or_default())
+    }
+
+    async fn item(&self, id: u64) -> Result<Item> {
+        self.items
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| anyhow!("item {} does not exist", id))
+    }
+
+    async fn user(&self, name: &str) -> Result<User> {
+        self.users
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("No user named {}", name))
+    }
+
+    async fn article(&self, url: &str) -> Result<String> {
+        self.pages
+            .get(url)
+            .cloned()
+            .ok_or_else(|| anyhow!("No page for {} in the fixture", url))
+    }
+
+    async fn search(
+        &self,
+        query: &SearchQuery,
+        page: usize,
+        hits_per_page: usize,
+    ) -> Result<SearchPage> {
+        let mut hits: Vec<&Item> = self
+            .items
+            .values()
+            .filter(|item| Self::matches(item, query))
+            .collect();
+        if query.by_date {
+            hits.sort_by_key(|item| std::cmp::Reverse(item.time));
+        } else {
+            hits.sort_by_key(|item| std::cmp::Reverse(item.score.unwrap_or(0)));
+        }
+
+        let hits_per_page = hits_per_page.max(1);
+        Ok(SearchPage {
+            pages: hits.len().div_ceil(hits_per_page),
+            items: hits
+                .into_iter()
+                .skip(page * hits_per_page)
+                .take(hits_per_page)
+                .cloned()
+                .collect(),
+        })
+    }
+}
+
+// ===== REQUEST COALESCING =====
+
+/// An item request in flight, shared by everyone waiting for the same item
+type PendingItem = Shared<BoxFuture<'static, Result<Item, String>>>;
+
+/// Wraps another client so concurrent requests for the same item share one
+/// request, as when a feed page, the detail pane and a live update overlap.
+///
+/// A [`fresh_item`](HnClient::fresh_item) request, as sent by a refresh, never
+/// joins one in flight. It takes that request's place, so later callers share it
+/// instead.
+#[derive(Debug)]
+pub struct CoalescingClient {
+    inner: Arc<dyn HnClient>,
+    /// Item requests in flight by id, each with the number it was started as
+    pending: Arc<Mutex<HashMap<u64, (u64, PendingItem)>>>,
+    /// Number of item requests started so far
+    started: AtomicU64,
+}
+
+impl CoalescingClient {
+    /// Coalesces the item requests of `inner`
+    pub fn new(inner: Arc<dyn HnClient>) -> Self {
+        Self {
+            inner,
+            pending: Arc::default(),
+            started: AtomicU64::new(0),
+        }
+    }
+
+    /// Requests an item, joining a request in flight for it unless `fresh`
+    async fn request(&self, id: u64, fresh: bool) -> Result<Item> {
+        let request = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            match pending.get(&id) {
+                Some((_, request)) if !fresh => request.clone(),
+                _ => {
+                    let number = self.started.fetch_add(1, Ordering::Relaxed);
+                    let request = self.start(id, number);
+                    pending.insert(id, (number, request.clone()));
+                    request
+                }
+            }
+        };
+        request.await.map_err(|e| anyhow!(e))
+    }
+
+    /// Starts a shared request for an item.
+    ///
+    /// The request forgets itself once done, so later calls fetch afresh, unless a
+    /// fresh request has replaced it meanwhile. It runs for whoever still waits if
+    /// its starter is cancelled.
+    fn start(&self, id: u64, number: u64) -> PendingItem {
+        let (inner, pending) = (self.inner.clone(), self.pending.clone());
+        async move {
+            let result = inner.item(id).await.map_err(|e| format!("{:#}", e));
+            let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+            if pending
+                .get(&id)
+                .is_some_and(|&(current, _)| current == number)
+            {
+                pending.remove(&id);
+            }
+            result
+        }
+        .boxed()
+        .shared()
+    }
+}
+
+#[async_trait]
+impl HnClient for CoalescingClient {
+    async fn feed_ids(&self, feed: Feed) -> Result<Vec<u64>> {
+        self.inner.feed_ids(feed).await
+    }
+
+    async fn item(&self, id: u64) -> Result<Item> {
+        self.request(id, false).await
+    }
+
+    async fn fresh_item(&self, id: u64) -> Result<Item> {
+        self.request(id, true).await
+    }
+
+    async fn user(&self, name: &str) -> Result<User> {
+        self.inner.user(name).await
+    }
+
+    async fn search(
+        &self,
+        query: &SearchQuery,
+        page: usize,
+        hits_per_page: usize,
+    ) -> Result<SearchPage> {
+        self.inner.search(query, page, hits_per_page).await
+    }
+
+    async fn article(&self, url: &str) -> Result<String> {
+        self.inner.article(url).await
+    }
+
+    async fn watch(&self, feed: Feed, on_event: &(dyn Fn(LiveEvent) + Send + Sync)) -> Result<()> {
+        self.inner.watch(feed, on_event).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future::join_all;
+
+    use super::*;
+
+    /// Fixture client that counts item requests and holds each one open briefly,
+    /// so concurrent requests overlap
+    #[derive(Debug)]
+    struct CountingClient {
+        fixture: FixtureClient,
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HnClient for CountingClient {
+        async fn feed_ids(&self, feed: Feed) -> Result<Vec<u64>> {
+            self.fixture.feed_ids(feed).await
+        }
+
+        async fn item(&self, id: u64) -> Result<Item> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.fixture.item(id).await
+        }
+
+        async fn user(&self, name: &str) -> Result<User> {
+            self.fixture.user(name).await
+        }
+
+        async fn search(
+            &self,
+            query: &SearchQuery,
+            page: usize,
+            hits_per_page: usize,
+        ) -> Result<SearchPage> {
+            self.fixture.search(query, page, hits_per_page).await
+        }
+
+        async fn article(&self, url: &str) -> Result<String> {
+            self.fixture.article(url).await
+        }
+    }
+
+    fn counting_client() -> Arc<CountingClient> {
+        let fixture = FixtureClient::from_json(
+            r#"{"items": [{"id": 1, "type": "story", "title": "One", "time": 0}]}"#,
+        )
+        .unwrap();
+        Arc::new(CountingClient {
+            fixture,
+            requests: AtomicUsize::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn concurrent_item_requests_are_sent_once() {
+        let inner = counting_client();
+        let client = CoalescingClient::new(inner.clone());
+
+        let items = join_all((0..5).map(|_| client.item(1))).await;
+        assert!(items.iter().all(|item| item.as_ref().unwrap().id == 1));
+        assert_eq!(inner.requests.load(Ordering::SeqCst), 1);
+
+        // A finished request is forgotten, so the next one fetches afresh
+        client.item(1).await.unwrap();
+        assert_eq!(inner.requests.load(Ordering::SeqCst), 2);
+        assert!(client.pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn coalesced_failures_reach_every_caller() {
+        let inner = counting_client();
+        let client = CoalescingClient::new(inner.clone());
+
+        let items = join_all((0..3).map(|_| client.item(2))).await;
+        for item in items {
+            assert_eq!(item.unwrap_err().to_string(), "item 2 does not exist");
+        }
+        assert_eq!(inner.requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn fresh_requests_do_not_join_earlier_ones() {
+        let inner = counting_client();
+        let client = CoalescingClient::new(inner.clone());
+
+        // The refresh starts its own request 10ms into an earlier one, and a caller
+        // arriving after the earlier one finished joins the refresh's request
+        let after = |ms| tokio::time::sleep(Duration::from_millis(ms));
+        let (earlier, fresh, later) = tokio::join!(
+            client.item(1),
+            async {
+                after(10).await;
+                client.fresh_item(1).await
+            },
+            async {
+                after(25).await;
+                client.item(1).await
+            }
+        );
+        assert!(earlier.is_ok() && fresh.is_ok() && later.is_ok());
+        assert_eq!(inner.requests.load(Ordering::SeqCst), 2);
+        assert!(client.pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn different_items_are_not_coalesced() {
+        let inner = counting_client();
+        let client = CoalescingClient::new(inner.clone());
+
+        let (one, two) = tokio::join!(client.item(1), client.item(2));
+        assert!(one.is_ok() && two.is_err());
+        assert_eq!(inner.requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn fixture_serves_feeds_items_and_users() {
+        let client = FixtureClient::from_json(
+            r#"{
+                "feeds": {"topstories": [2, 1]},
+                "items": [{"id": 1, "type": "story", "title": "One", "time": 0}],
+                "users": [{"id": "pg", "created": 0, "karma": 1}]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(client.feed_ids(Feed::Top).await.unwrap(), vec![2, 1]);
+        assert!(client.feed_ids(Feed::New).await.unwrap().is_empty());
+        assert_eq!(client.item(1).await.unwrap().title.as_deref(), Some("One"));
+        assert!(client.item(2).await.is_err());
+        assert_eq!(client.user("pg").await.unwrap().karma, 1);
+        assert!(client.user("nobody").await.is_err());
+    }
+}
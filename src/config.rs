@@ -0,0 +1,325 @@
+//! User configuration.
+//!
+//! Settings are read from `config.toml` in the XDG config directory
+//! (`~/.config/hn/config.toml` on Linux). Every key is optional and falls back to
+//! the built-in default, while unknown keys and invalid values are rejected at
+//! startup so typos do not go unnoticed.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+
+use crate::keymap::{KeySettings, Keymap};
+use crate::killfile::{FilterSettings, Killfile};
+use crate::Feed;
+
+/// Default number of stories fetched per page
+pub const DEFAULT_PAGE_SIZE: usize = 30;
+
+/// Default number of item requests kept in flight at once
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Default timeout of a single HTTP request (seconds)
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Largest accepted page size; the feeds themselves hold 500 ids at most
+const MAX_PAGE_SIZE: usize = 500;
+
+/// Largest accepted number of parallel requests
+const MAX_CONCURRENCY: usize = 64;
+
+/// All user settings
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Colors of the story list
+    pub theme: Theme,
+    /// Which feed to open and how much of it to load
+    pub feeds: FeedSettings,
+    /// Timeouts and request limits
+    pub network: NetworkSettings,
+    /// Display toggles
+    pub ui: UiSettings,
+    /// Stories hidden from the feeds
+    pub filters: FilterSettings,
+    /// Key binding overrides
+    pub keys: KeySettings,
+}
+
+/// Colors of the story list, as names (`"light blue"`), `#rrggbb` or 0-255 indexes
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Story titles
+    #[serde(deserialize_with = "color")]
+    pub title: Color,
+    /// Domains and URLs
+    #[serde(deserialize_with = "color")]
+    pub url: Color,
+    /// Scores
+    #[serde(deserialize_with = "color")]
+    pub score: Color,
+    /// Author names
+    #[serde(deserialize_with = "color")]
+    pub author: Color,
+    /// Comment counts
+    #[serde(deserialize_with = "color")]
+    pub comments: Color,
+    /// Post times
+    #[serde(deserialize_with = "color")]
+    pub time: Color,
+    /// Ranks, separators and other secondary text
+    #[serde(deserialize_with = "color")]
+    pub muted: Color,
+    /// Titles of stories already opened
+    #[serde(deserialize_with = "color")]
+    pub visited: Color,
+    /// Border of the story list; other feeds than Top use their accent color
+    #[serde(deserialize_with = "color")]
+    pub border: Color,
+    /// Background of the selected story, instead of the feed's own color
+    #[serde(deserialize_with = "optional_color")]
+    pub selection: Option<Color>,
+    /// Text of the selected story, instead of the feed's own color
+    #[serde(deserialize_with = "optional_color")]
+    pub selection_text: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            title: Color::White,
+            url: Color::Blue,
+            score: Color::Green,
+            author: Color::Magenta,
+            comments: Color::Cyan,
+            time: Color::Yellow,
+            muted: Color::DarkGray,
+            visited: Color::Gray,
+            border: Color::White,
+            selection: None,
+            selection_text: None,
+        }
+    }
+}
+
+/// Feed settings
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedSettings {
+    /// Feed shown at startup: top, new, best, ask, show or jobs
+    #[serde(deserialize_with = "feed")]
+    pub default: Feed,
+    /// Number of stories fetched per page
+    pub page_size: usize,
+}
+
+impl Default for FeedSettings {
+    fn default() -> Self {
+        Self {
+            default: Feed::Top,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// Network settings
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSettings {
+    /// Timeout of a single HTTP request (seconds)
+    pub timeout_secs: u64,
+    /// Maximum number of item requests in flight at once
+    pub concurrency: usize,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+/// Display toggles
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiSettings {
+    /// Show score, author, comment count and age under each title at startup
+    pub show_info: bool,
+    /// Show the detail pane beside the story list on wide terminals
+    pub split_view: bool,
+    /// Follow the current feed live while online
+    pub live_updates: bool,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            show_info: false,
+            split_view: true,
+            live_updates: true,
+        }
+    }
+}
+
+impl Config {
+    /// Default location of the config file
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("hn").join("config.toml"))
+    }
+
+    /// Loads the config file at `path`, or at the default location if `path` is
+    /// `None`. A missing default file yields the built-in defaults.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("Cannot read config {}", path.display()))
+            }
+        };
+        Self::parse(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Parses and validates config TOML
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks values that parse but make no sense
+    fn validate(&self) -> Result<()> {
+        let page_size = self.feeds.page_size;
+        if !(1..=MAX_PAGE_SIZE).contains(&page_size) {
+            bail!(
+                "feeds.page_size must be between 1 and {}, got {}",
+                MAX_PAGE_SIZE,
+                page_size
+            );
+        }
+        if self.network.timeout_secs == 0 {
+            bail!("network.timeout_secs must be at least 1");
+        }
+        let concurrency = self.network.concurrency;
+        if !(1..=MAX_CONCURRENCY).contains(&concurrency) {
+            bail!(
+                "network.concurrency must be between 1 and {}, got {}",
+                MAX_CONCURRENCY,
+                concurrency
+            );
+        }
+        Killfile::in_memory(&self.filters)?;
+        Keymap::new(&self.keys)?;
+        Ok(())
+    }
+}
+
+/// A color as written in the config file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorValue {
+    /// A bare palette index, such as `208`
+    Index(i64),
+    /// A name, `#rrggbb` value or quoted index
+    Text(String),
+}
+
+/// Deserializes a color name, `#rrggbb` value or 0-255 index, quoted or not
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let value = match ColorValue::deserialize(deserializer)? {
+        ColorValue::Index(index) => {
+            return u8::try_from(index).map(Color::Indexed).map_err(|_| {
+                serde::de::Error::custom(format!(
+                    "color index {} is out of range, expected 0-255",
+                    index
+                ))
+            })
+        }
+        ColorValue::Text(value) => value,
+    };
+    Color::from_str(&value).map_err(|_| {
+        serde::de::Error::custom(format!(
+            "unknown color \"{}\", expected a name like \"light blue\", \"#rrggbb\" or 0-255",
+            value
+        ))
+    })
+}
+
+/// Deserializes a color that may be left out
+fn optional_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+    color(deserializer).map(Some)
+}
+
+/// Deserializes a feed by its short name
+fn feed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Feed, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Feed::ALL
+        .into_iter()
+        .find(|feed| feed.name() == value.to_lowercase())
+        .ok_or_else(|| {
+            let names: Vec<&str> = Feed::ALL.iter().map(|feed| feed.name()).collect();
+            serde::de::Error::custom(format!(
+                "unknown feed \"{}\", expected one of {}",
+                value,
+                names.join(", ")
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(toml: &str) -> Result<Theme, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    #[test]
+    fn colors_accept_names_hex_and_indexes() {
+        let theme = theme(
+            r##"
+            title = 208
+            url = "33"
+            score = "#ff6600"
+            author = "light blue"
+            selection = 0
+            "##,
+        )
+        .unwrap();
+        assert_eq!(theme.title, Color::Indexed(208));
+        assert_eq!(theme.url, Color::Indexed(33));
+        assert_eq!(theme.score, Color::Rgb(0xff, 0x66, 0x00));
+        assert_eq!(theme.author, Color::LightBlue);
+        assert_eq!(theme.selection, Some(Color::Indexed(0)));
+    }
+
+    #[test]
+    fn invalid_colors_are_reported() {
+        let error = theme("title = 256").unwrap_err().to_string();
+        assert!(
+            error.contains("color index 256 is out of range"),
+            "{}",
+            error
+        );
+        let error = theme("title = -1").unwrap_err().to_string();
+        assert!(error.contains("out of range"), "{}", error);
+        let error = theme(r#"title = "mauve""#).unwrap_err().to_string();
+        assert!(error.contains("unknown color \"mauve\""), "{}", error);
+        assert!(theme("title = true").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Incremental filtering of story lists.
+//!
+//! A filter is the text typed after `/`. Every word of it must occur, ignoring
+//! case, in a story's title, domain or author for the story to stay in the list,
+//! and the occurrences are highlighted where those fields are drawn.
+
+use ratatui::style::Style;
+use ratatui::text::Span;
+
+use crate::Item;
+
+/// Lowercase words of a filter
+pub fn words(filter: &str) -> Vec<String> {
+    filter.split_whitespace().map(str::to_lowercase).collect()
+}
+
+/// Whether an item matches every word of a filter
+pub fn matches(item: &Item, words: &[String]) -> bool {
+    if words.is_empty() {
+        return true;
+    }
+    let title = item.display_title().to_lowercase();
+    let domain = item.domain().unwrap_or_default().to_lowercase();
+    let author = item.author().to_lowercase();
+    words.iter().all(|word| {
+        title.contains(word.as_str())
+            || domain.contains(word.as_str())
+            || author.contains(word.as_str())
+    })
+}
+
+/// Splits `text` into spans in `style`, with the occurrences of the filter
+/// words in `highlight` on top of it
+pub fn highlight<'a>(
+    text: String,
+    words: &[String],
+    style: Style,
+    highlight: Style,
+) -> Vec<Span<'a>> {
+    let ranges = match_ranges(&text, words);
+    if ranges.is_empty() {
+        return vec![Span::styled(text, style)];
+    }
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for (start, stop) in ranges {
+        if start > end {
+            spans.push(Span::styled(text[end..start].to_string(), style));
+        }
+        spans.push(Span::styled(
+            text[start..stop].to_string(),
+            style.patch(highlight),
+        ));
+        end = stop;
+    }
+    if end < text.len() {
+        spans.push(Span::styled(text[end..].to_string(), style));
+    }
+    spans
+}
+
+/// Sorted, merged byte ranges of the words' occurrences in `text`
+fn match_ranges(text: &str, words: &[String]) -> Vec<(usize, usize)> {
+    let lower = text.to_lowercase();
+    // Lowercasing changed some lengths, so offsets would not line up
+    if lower.len() != text.len() {
+        return Vec::new();
+    }
+    let mut ranges: Vec<(usize, usize)> = words
+        .iter()
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            lower
+                .match_indices(word.as_str())
+                .map(|(start, found)| (start, start + found.len()))
+        })
+        .filter(|&(start, stop)| text.is_char_boundary(start) && text.is_char_boundary(stop))
+        .collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, stop) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(stop),
+            _ => merged.push((start, stop)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier};
+
+    use super::*;
+
+    fn item(title: &str, url: &str, by: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "id": 1, "type": "story", "time": 0, "title": title, "url": url, "by": by
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn every_word_must_match_some_field() {
+        let item = item("Show HN: A Rust TUI", "https://github.com/x/y", "Alice");
+        let check = |filter| matches(&item, &words(filter));
+        assert!(check(""));
+        assert!(check("rust"));
+        assert!(check("RUST tui"));
+        // Words may match different fields
+        assert!(check("rust github alice"));
+        assert!(!check("rust python"));
+        assert!(!check("bob"));
+    }
+
+    #[test]
+    fn ranges_are_sorted_and_merged() {
+        let words = words("ab bc rust");
+        assert_eq!(match_ranges("abc rust", &words), vec![(0, 3), (4, 8)]);
+        // Adjacent and repeated occurrences merge too
+        assert_eq!(match_ranges("rustrust ab", &words), vec![(0, 8), (9, 11)]);
+        assert_eq!(match_ranges("Rust and RUST", &words), vec![(0, 4), (9, 13)]);
+        assert!(match_ranges("nothing here", &words).is_empty());
+    }
+
+    #[test]
+    fn highlighting_is_off_when_lowercasing_changes_lengths() {
+        // "İ" lowercases to two characters, which would shift the offsets
+        assert!(match_ranges("İstanbul rust", &words("rust")).is_empty());
+        // Non-ASCII text of unchanged length still highlights
+        assert_eq!(match_ranges("Café Rust", &words("café")), vec![(0, 5)]);
+    }
+
+    #[test]
+    fn highlight_splits_spans() {
+        let style = Style::default().fg(Color::White);
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let spans = highlight("A Rust TUI".to_string(), &words("rust"), style, bold);
+        assert_eq!(
+            spans,
+            vec![
+                Span::styled("A ", style),
+                Span::styled("Rust", style.patch(bold)),
+                Span::styled(" TUI", style),
+            ]
+        );
+        let spans = highlight("İstanbul".to_string(), &words("stan"), style, bold);
+        assert_eq!(spans, vec![Span::styled("İstanbul", style)]);
+    }
+}
@@ -0,0 +1,331 @@
+//! Reading history.
+//!
+//! Remembers which items were opened, with their comment count at the time, and
+//! the stories last seen in each feed, in `history.json` under the XDG data
+//! directory. Lists use it to dim visited stories and to mark stories that are new
+//! since the last visit or have gained comments since they were read.
+//!
+//! The history is a convenience: a corrupt file starts a fresh one. Changes are
+//! written a moment after they settle, as [`Snapshot`]s that the caller writes off
+//! the UI thread.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{Feed, Item};
+
+/// Visits remembered at most; the oldest are forgotten first
+const MAX_VISITS: usize = 10_000;
+
+/// How long changes must settle before the history is written
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// Numbers snapshots in the order they were taken
+static SNAPSHOTS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of the newest snapshot written to each file, locked while writing so
+/// writes never overlap and an older snapshot never replaces a newer one
+static WRITTEN: Mutex<BTreeMap<PathBuf, u64>> = Mutex::new(BTreeMap::new());
+
+/// An opened item
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Visit {
+    /// Unix timestamp of the last time it was opened
+    pub at: u64,
+    /// Its comment count at that time
+    pub comments: u32,
+}
+
+/// What is written to disk
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct Stored {
+    /// Opened items by id
+    visits: HashMap<u64, Visit>,
+    /// Ids of the stories last loaded in each feed, in rank order
+    feeds: HashMap<Feed, Vec<u64>>,
+}
+
+/// Visited items and the last seen stories of every feed
+#[derive(Debug, Default)]
+pub struct History {
+    /// File the history is written to, or `None` to keep it in memory only
+    path: Option<PathBuf>,
+    stored: Stored,
+    /// Stories of each feed at the previous visit, which newer ones are compared to
+    baseline: HashMap<Feed, HashSet<u64>>,
+    /// When the first change not yet written was made
+    changed_at: Option<Instant>,
+}
+
+/// The serialized history, to be written off the UI thread
+#[derive(Debug)]
+pub struct Snapshot {
+    path: PathBuf,
+    data: Vec<u8>,
+    /// Order in which the snapshot was taken
+    number: u64,
+}
+
+impl History {
+    /// Opens the history at its default location
+    pub fn open() -> Self {
+        match dirs::data_dir() {
+            Some(dir) => Self::load(dir.join("hn").join("history.json")),
+            None => Self::in_memory(),
+        }
+    }
+
+    /// Opens the history file at `path`
+    pub fn load(path: PathBuf) -> Self {
+        let stored: Stored = std::fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        let baseline = stored
+            .feeds
+            .iter()
+            .map(|(&feed, ids)| (feed, ids.iter().copied().collect()))
+            .collect();
+        Self {
+            path: Some(path),
+            stored,
+            baseline,
+            changed_at: None,
+        }
+    }
+
+    /// A history that is never written to disk
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// The last visit of an item, if it was ever opened
+    pub fn visit(&self, id: u64) -> Option<Visit> {
+        self.stored.visits.get(&id).copied()
+    }
+
+    /// Number of comments an opened item has gained since it was last opened
+    pub fn new_comments(&self, item: &Item) -> u32 {
+        match (self.visit(item.id), item.descendants) {
+            (Some(visit), Some(count)) => count.saturating_sub(visit.comments),
+            _ => 0,
+        }
+    }
+
+    /// Whether a story was not in the feed at the previous visit. Nothing is new
+    /// in a feed that was never seen before.
+    pub fn is_new(&self, feed: Feed, id: u64) -> bool {
+        self.baseline
+            .get(&feed)
+            .is_some_and(|seen| !seen.contains(&id))
+    }
+
+    /// Records that an item was opened
+    pub fn record_visit(&mut self, item: &Item) {
+        let visit = Visit {
+            at: now(),
+            comments: item.descendants.unwrap_or(0),
+        };
+        self.stored.visits.insert(item.id, visit);
+        if self.stored.visits.len() > MAX_VISITS {
+            let mut times: Vec<u64> = self.stored.visits.values().map(|v| v.at).collect();
+            times.sort_unstable();
+            let cutoff = times[times.len() - MAX_VISITS];
+            self.stored.visits.retain(|_, visit| visit.at >= cutoff);
+        }
+        self.changed();
+    }
+
+    /// Records the stories currently loaded in a feed
+    pub fn record_feed(&mut self, feed: Feed, ids: Vec<u64>) {
+        if self.stored.feeds.get(&feed) != Some(&ids) {
+            self.stored.feeds.insert(feed, ids);
+            self.changed();
+        }
+    }
+
+    /// Makes the stories last recorded for a feed the ones newer stories are
+    /// compared to, as when the feed is refreshed
+    pub fn mark_seen(&mut self, feed: Feed) {
+        if let Some(ids) = self.stored.feeds.get(&feed) {
+            self.baseline.insert(feed, ids.iter().copied().collect());
+        }
+    }
+
+    /// The history to write, once changes have settled for [`SAVE_DELAY`]
+    pub fn pending_save(&mut self) -> Option<Snapshot> {
+        if self.changed_at?.elapsed() < SAVE_DELAY {
+            return None;
+        }
+        self.take_unsaved()
+    }
+
+    /// The history to write right away if anything changed since the last
+    /// snapshot, as when quitting
+    pub fn take_unsaved(&mut self) -> Option<Snapshot> {
+        self.changed_at.take()?;
+        Some(Snapshot {
+            path: self.path.clone()?,
+            data: serde_json::to_vec(&self.stored).ok()?,
+            number: SNAPSHOTS.fetch_add(1, Ordering::Relaxed) + 1,
+        })
+    }
+
+    /// Notes a change to be written
+    fn changed(&mut self) {
+        self.changed_at.get_or_insert_with(Instant::now);
+    }
+}
+
+impl Snapshot {
+    /// Writes the snapshot unless a newer one was written already. Blocks, so it
+    /// belongs on a blocking thread.
+    pub fn write(self) -> Result<()> {
+        let mut written = WRITTEN.lock().unwrap_or_else(PoisonError::into_inner);
+        if written
+            .get(&self.path)
+            .is_some_and(|&newest| newest > self.number)
+        {
+            return Ok(());
+        }
+        write(&self.path, &self.data)
+            .with_context(|| format!("Cannot save history to {}", self.path.display()))?;
+        written.insert(self.path, self.number);
+        Ok(())
+    }
+}
+
+/// Writes serialized history to `path` through a temporary file
+fn write(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Current Unix time in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(id: u64, comments: u32) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "type": "story", "time": 0, "descendants": comments
+        }))
+        .unwrap()
+    }
+
+    /// A history file path in a fresh temporary directory
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("hn-history-test-{}-{}", std::process::id(), name))
+            .join("history.json")
+    }
+
+    #[test]
+    fn new_comments_count_since_the_last_visit() {
+        let mut history = History::in_memory();
+        assert_eq!(history.new_comments(&story(1, 10)), 0);
+        assert!(history.visit(1).is_none());
+
+        history.record_visit(&story(1, 10));
+        assert_eq!(history.new_comments(&story(1, 10)), 0);
+        assert_eq!(history.new_comments(&story(1, 14)), 4);
+        // Deleted comments never count as negative
+        assert_eq!(history.new_comments(&story(1, 7)), 0);
+
+        history.record_visit(&story(1, 14));
+        assert_eq!(history.new_comments(&story(1, 15)), 1);
+    }
+
+    #[test]
+    fn stories_are_new_against_the_previous_visit() {
+        let mut history = History::in_memory();
+        // Nothing is new in a feed never seen before
+        assert!(!history.is_new(Feed::Top, 1));
+
+        history.record_feed(Feed::Top, vec![1, 2]);
+        assert!(!history.is_new(Feed::Top, 3));
+        history.mark_seen(Feed::Top);
+        assert!(!history.is_new(Feed::Top, 1));
+        assert!(history.is_new(Feed::Top, 3));
+        assert!(!history.is_new(Feed::New, 3));
+
+        // Recording the feed again keeps the baseline until it is marked seen
+        history.record_feed(Feed::Top, vec![3, 1]);
+        assert!(history.is_new(Feed::Top, 3));
+        history.mark_seen(Feed::Top);
+        assert!(!history.is_new(Feed::Top, 3));
+        assert!(history.is_new(Feed::Top, 2));
+    }
+
+    #[test]
+    fn oldest_visits_are_forgotten_first() {
+        let mut history = History::in_memory();
+        for id in 0..MAX_VISITS as u64 {
+            let visit = Visit {
+                at: id + 1,
+                comments: 0,
+            };
+            history.stored.visits.insert(id, visit);
+        }
+        history.record_visit(&story(u64::MAX, 0));
+        assert_eq!(history.stored.visits.len(), MAX_VISITS);
+        assert!(history.visit(0).is_none());
+        assert!(history.visit(1).is_some());
+        assert!(history.visit(u64::MAX).is_some());
+    }
+
+    #[test]
+    fn changes_are_saved_once_they_settle() {
+        let path = temporary_path("settle");
+        let _ = std::fs::remove_file(&path);
+        let mut history = History::load(path.clone());
+        assert!(history.take_unsaved().is_none());
+
+        history.record_visit(&story(1, 5));
+        history.record_feed(Feed::Top, vec![1, 2]);
+        assert!(history.pending_save().is_none());
+        history.changed_at = Some(Instant::now() - SAVE_DELAY);
+        let snapshot = history.pending_save().unwrap();
+        assert!(history.pending_save().is_none());
+
+        // A snapshot taken later wins even if an earlier one is written after it
+        history.record_visit(&story(2, 3));
+        history.take_unsaved().unwrap().write().unwrap();
+        snapshot.write().unwrap();
+        let reloaded = History::load(path.clone());
+        assert_eq!(reloaded.visit(1).unwrap().comments, 5);
+        assert_eq!(reloaded.visit(2).unwrap().comments, 3);
+        assert!(reloaded.is_new(Feed::Top, 3));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn failed_writes_are_reported() {
+        // A file where the directory should be
+        let blocker = temporary_path("blocked").parent().unwrap().to_path_buf();
+        std::fs::write(&blocker, "").unwrap();
+        let mut history = History::load(blocker.join("history.json"));
+        history.record_visit(&story(1, 0));
+        let error = history.take_unsaved().unwrap().write().unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Cannot save history to"));
+        let _ = std::fs::remove_file(&blocker);
+    }
+}
@@ -0,0 +1,989 @@
+//! Key bindings.
+//!
+//! Every screen has its own [`Keymap`] layer mapping key sequences such as `j`,
+//! `ctrl-n` or `g g` to [`Action`]s. The defaults can be overridden per screen,
+//! or for all screens at once, from the `[keys]` section of the config file.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::{anyhow, bail, Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Something the user can do with a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Back,
+    Down,
+    Up,
+    Top,
+    Bottom,
+    PageDown,
+    PageUp,
+    Read,
+    OpenBrowser,
+    Comments,
+    ReadPost,
+    Profile,
+    Search,
+    EditQuery,
+    Refresh,
+    ToggleInfo,
+    NextFeed,
+    PreviousFeed,
+    FeedTop,
+    FeedNew,
+    FeedBest,
+    FeedAsk,
+    FeedShow,
+    FeedJobs,
+    ToggleShow,
+    Parent,
+    FirstReply,
+    NextSibling,
+    PreviousSibling,
+    Collapse,
+    Bookmark,
+    Saved,
+    Filter,
+    NextMatch,
+    PreviousMatch,
+    CycleSort,
+    EditTags,
+    EditNote,
+    HideDomain,
+    HideUser,
+    RevealHidden,
+    Help,
+    CommandPalette,
+}
+
+impl Action {
+    /// Every action, in the order they are listed in help
+    pub const ALL: [Action; 44] = [
+        Action::Quit,
+        Action::Back,
+        Action::Down,
+        Action::Up,
+        Action::Top,
+        Action::Bottom,
+        Action::PageDown,
+        Action::PageUp,
+        Action::Read,
+        Action::OpenBrowser,
+        Action::Comments,
+        Action::ReadPost,
+        Action::Profile,
+        Action::Search,
+        Action::EditQuery,
+        Action::Refresh,
+        Action::ToggleInfo,
+        Action::NextFeed,
+        Action::PreviousFeed,
+        Action::FeedTop,
+        Action::FeedNew,
+        Action::FeedBest,
+        Action::FeedAsk,
+        Action::FeedShow,
+        Action::FeedJobs,
+        Action::ToggleShow,
+        Action::Parent,
+        Action::FirstReply,
+        Action::NextSibling,
+        Action::PreviousSibling,
+        Action::Collapse,
+        Action::Bookmark,
+        Action::Saved,
+        Action::Filter,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::CycleSort,
+        Action::EditTags,
+        Action::EditNote,
+        Action::HideDomain,
+        Action::HideUser,
+        Action::RevealHidden,
+        Action::Help,
+        Action::CommandPalette,
+    ];
+
+    /// Name of the action in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Back => "back",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::Read => "read",
+            Action::OpenBrowser => "open_browser",
+            Action::Comments => "comments",
+            Action::ReadPost => "read_post",
+            Action::Profile => "profile",
+            Action::Search => "search",
+            Action::EditQuery => "edit_query",
+            Action::Refresh => "refresh",
+            Action::ToggleInfo => "toggle_info",
+            Action::NextFeed => "next_feed",
+            Action::PreviousFeed => "previous_feed",
+            Action::FeedTop => "feed_top",
+            Action::FeedNew => "feed_new",
+            Action::FeedBest => "feed_best",
+            Action::FeedAsk => "feed_ask",
+            Action::FeedShow => "feed_show",
+            Action::FeedJobs => "feed_jobs",
+            Action::ToggleShow => "toggle_show",
+            Action::Parent => "parent",
+            Action::FirstReply => "first_reply",
+            Action::NextSibling => "next_sibling",
+            Action::PreviousSibling => "previous_sibling",
+            Action::Collapse => "collapse",
+            Action::Bookmark => "bookmark",
+            Action::Saved => "saved",
+            Action::Filter => "filter",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::CycleSort => "cycle_sort",
+            Action::EditTags => "edit_tags",
+            Action::EditNote => "edit_note",
+            Action::HideDomain => "hide_domain",
+            Action::HideUser => "hide_user",
+            Action::RevealHidden => "reveal_hidden",
+            Action::Help => "help",
+            Action::CommandPalette => "command_palette",
+        }
+    }
+
+    /// What the action does, as shown in help and the command palette
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Back => "Go back",
+            Action::Down => "Move down",
+            Action::Up => "Move up",
+            Action::Top => "Jump to the top",
+            Action::Bottom => "Jump to the bottom",
+            Action::PageDown => "Scroll a page down",
+            Action::PageUp => "Scroll a page up",
+            Action::Read => "Read the article in the terminal",
+            Action::OpenBrowser => "Open the link in a browser",
+            Action::Comments => "Read the comments",
+            Action::ReadPost => "Read the post's text in full",
+            Action::Profile => "View the author's profile",
+            Action::Search => "Search stories and comments",
+            Action::EditQuery => "Edit the search query",
+            Action::Refresh => "Refresh",
+            Action::ToggleInfo => "Show or hide score, author and comments",
+            Action::NextFeed => "Next feed",
+            Action::PreviousFeed => "Previous feed",
+            Action::FeedTop => "Switch to Top stories",
+            Action::FeedNew => "Switch to New stories",
+            Action::FeedBest => "Switch to Best stories",
+            Action::FeedAsk => "Switch to Ask HN",
+            Action::FeedShow => "Switch to Show HN",
+            Action::FeedJobs => "Switch to Jobs",
+            Action::ToggleShow => "Toggle between Top and Show HN",
+            Action::Parent => "Jump to the parent comment",
+            Action::FirstReply => "Jump to the first reply",
+            Action::NextSibling => "Next comment at the same depth",
+            Action::PreviousSibling => "Previous comment at the same depth",
+            Action::Collapse => "Collapse or expand replies",
+            Action::Bookmark => "Save or unsave the story",
+            Action::Saved => "Show saved stories",
+            Action::Filter => "Filter the list",
+            Action::NextMatch => "Jump to the next match of the filter",
+            Action::PreviousMatch => "Jump to the previous match of the filter",
+            Action::CycleSort => "Sort by rank, score, comments, age, points per hour or domain",
+            Action::EditTags => "Edit the tags of the saved story",
+            Action::EditNote => "Edit the note of the saved story",
+            Action::HideDomain => "Hide all stories from this domain",
+            Action::HideUser => "Hide all stories by this user",
+            Action::RevealHidden => "Show or hide the stories the killfile hides",
+            Action::Help => "Show key bindings",
+            Action::CommandPalette => "Run a command by name",
+        }
+    }
+
+    /// Looks up an action by its config name
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// Screens with their own bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Stories,
+    Search,
+    User,
+    Comments,
+    Reader,
+    Saved,
+}
+
+impl Mode {
+    /// Every mode, in config section order
+    pub const ALL: [Mode; 6] = [
+        Mode::Stories,
+        Mode::Search,
+        Mode::User,
+        Mode::Comments,
+        Mode::Reader,
+        Mode::Saved,
+    ];
+
+    /// Name of the mode's config section under `[keys]`
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Stories => "stories",
+            Mode::Search => "search",
+            Mode::User => "user",
+            Mode::Comments => "comments",
+            Mode::Reader => "reader",
+            Mode::Saved => "saved",
+        }
+    }
+
+    /// Built-in bindings of the mode, as `(keys, action)` pairs
+    fn defaults(self) -> &'static [(&'static str, Action)] {
+        use Action::*;
+        match self {
+            Mode::Stories => &[
+                ("q", Quit),
+                ("Q", Quit),
+                ("esc", Quit),
+                ("j", Down),
+                ("down", Down),
+                ("k", Up),
+                ("up", Up),
+                ("g g", Top),
+                ("home", Top),
+                ("G", Bottom),
+                ("end", Bottom),
+                ("enter", Read),
+                ("o", OpenBrowser),
+                ("O", OpenBrowser),
+                ("c", Comments),
+                ("C", Comments),
+                ("u", Profile),
+                ("U", Profile),
+                ("f", Search),
+                ("F", Search),
+                ("r", Refresh),
+                ("R", Refresh),
+                ("i", ToggleInfo),
+                ("I", ToggleInfo),
+                ("tab", NextFeed),
+                ("backtab", PreviousFeed),
+                ("1", FeedTop),
+                ("2", FeedNew),
+                ("3", FeedBest),
+                ("4", FeedAsk),
+                ("5", FeedShow),
+                ("6", FeedJobs),
+                ("s", ToggleShow),
+                ("S", ToggleShow),
+                ("b", Bookmark),
+                ("B", Saved),
+                ("x", HideDomain),
+                ("X", HideUser),
+                ("H", RevealHidden),
+                ("/", Filter),
+                ("n", NextMatch),
+                ("N", PreviousMatch),
+                ("t", CycleSort),
+                ("?", Help),
+                (":", CommandPalette),
+            ],
+            Mode::Search => &[
+                ("q", Back),
+                ("Q", Back),
+                ("esc", Back),
+                ("j", Down),
+                ("down", Down),
+                ("k", Up),
+                ("up", Up),
+                ("g g", Top),
+                ("home", Top),
+                ("G", Bottom),
+                ("end", Bottom),
+                ("enter", Read),
+                ("o", OpenBrowser),
+                ("O", OpenBrowser),
+                ("c", Comments),
+                ("C", Comments),
+                ("u", Profile),
+                ("U", Profile),
+                ("f", EditQuery),
+                ("F", EditQuery),
+                ("i", ToggleInfo),
+                ("I", ToggleInfo),
+                ("b", Bookmark),
+                ("?", Help),
+                (":", CommandPalette),
+            ],
+            Mode::User => &[
+                ("q", Back),
+                ("Q", Back),
+                ("esc", Back),
+                ("backspace", Back),
+                ("j", Down),
+                ("down", Down),
+                ("k", Up),
+                ("up", Up),
+                ("g g", Top),
+                ("home", Top),
+                ("G", Bottom),
+                ("end", Bottom),
+                ("enter", Read),
+                ("o", OpenBrowser),
+                ("O", OpenBrowser),
+                ("c", Comments),
+                ("C", Comments),
+                ("i", ToggleInfo),
+                ("I", ToggleInfo),
+                ("b", Bookmark),
+                ("/", Filter),
+                ("n", NextMatch),
+                ("N", PreviousMatch),
+                ("t", CycleSort),
+                ("r", Refresh),
+                ("R", Refresh),
+                ("?", Help),
+                (":", CommandPalette),
+            ],
+            Mode::Comments => &[
+                ("q", Back),
+                ("Q", Back),
+                ("esc", Back),
+                ("backspace", Back),
+                ("j", Down),
+                ("down", Down),
+                ("k", Up),
+                ("up", Up),
+                ("g g", Top),
+                ("home", Top),
+                ("G", Bottom),
+                ("end", Bottom),
+                ("h", Parent),
+                ("left", Parent),
+                ("l", FirstReply),
+                ("right", FirstReply),
+                ("J", NextSibling),
+                ("K", PreviousSibling),
+                ("space", Collapse),
+                ("enter", Collapse),
+                ("u", Profile),
+                ("U", Profile),
+                ("o", OpenBrowser),
+                ("O", OpenBrowser),
+                ("p", ReadPost),
+                ("P", ReadPost),
+                ("r", Refresh),
+                ("R", Refresh),
+                ("b", Bookmark),
+                ("?", Help),
+                (":", CommandPalette),
+            ],
+            Mode::Reader => &[
+                ("q", Back),
+                ("Q", Back),
+                ("esc", Back),
+                ("backspace", Back),
+                ("j", Down),
+                ("down", Down),
+                ("k", Up),
+                ("up", Up),
+                ("space", PageDown),
+                ("pagedown", PageDown),
+                ("b", PageUp),
+                ("pageup", PageUp),
+                ("g g", Top),
+                ("home", Top),
+                ("G", Bottom),
+                ("end", Bottom),
+                ("o", OpenBrowser),
+                ("O", OpenBrowser),
+                ("c", Comments),
+                ("C", Comments),
+                ("?", Help),
+                (":", CommandPalette),
+            ],
+            Mode::Saved => &[
+                ("q", Back),
+                ("Q", Back),
+                ("esc", Back),
+                ("backspace", Back),
+                ("j", Down),
+                ("down", Down),
+                ("k", Up),
+                ("up", Up),
+                ("g g", Top),
+                ("home", Top),
+                ("G", Bottom),
+                ("end", Bottom),
+                ("enter", Read),
+                ("o", OpenBrowser),
+                ("O", OpenBrowser),
+                ("c", Comments),
+                ("C", Comments),
+                ("u", Profile),
+                ("U", Profile),
+                ("i", ToggleInfo),
+                ("I", ToggleInfo),
+                ("d", Bookmark),
+                ("delete", Bookmark),
+                ("/", Filter),
+                ("#", EditTags),
+                ("e", EditNote),
+                ("?", Help),
+                (":", CommandPalette),
+            ],
+        }
+    }
+}
+
+/// One key press with its modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Normalizes a key event: the case of a character already says whether Shift
+    /// was held, and terminals disagree on whether they report it separately
+    pub fn from_event(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if !matches!(event.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers |= event.modifiers & KeyModifiers::SHIFT;
+        }
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+
+    /// Parses a single key such as `j`, `G`, `enter`, `ctrl-n` or `alt-left`
+    fn parse(text: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("c-") && rest.len() > 2 {
+                (KeyModifiers::CONTROL, 2)
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, 4)
+            } else if lower.starts_with("m-") && rest.len() > 2 {
+                (KeyModifiers::ALT, 2)
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => bail!("unknown key \"{}\"", text),
+                },
+            },
+        };
+
+        // Shift+Tab arrives as BackTab, and shifted characters as their upper case
+        let code = match code {
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        let event = KeyEvent::new(code, modifiers);
+        Ok(Self::from_event(event))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// A sequence of key presses bound to an action
+type Sequence = Vec<KeyChord>;
+
+/// Parses a space-separated key sequence such as `g g` or `ctrl-x ctrl-c`
+fn parse_sequence(text: &str) -> Result<Sequence> {
+    let sequence = text
+        .split_whitespace()
+        .map(KeyChord::parse)
+        .collect::<Result<Sequence>>()?;
+    if sequence.is_empty() {
+        bail!("empty key binding");
+    }
+    Ok(sequence)
+}
+
+/// Formats a key sequence for display
+pub fn format_sequence(sequence: &[KeyChord]) -> String {
+    sequence
+        .iter()
+        .map(KeyChord::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Keys bound to one action in the config file: a single sequence or a list
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Bindings {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Bindings {
+    fn sequences(&self) -> &[String] {
+        match self {
+            Bindings::One(keys) => std::slice::from_ref(keys),
+            Bindings::Many(keys) => keys,
+        }
+    }
+}
+
+/// The `[keys]` config section: action names mapped to keys, per screen and for
+/// every screen at once
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeySettings {
+    all: HashMap<String, Bindings>,
+    stories: HashMap<String, Bindings>,
+    search: HashMap<String, Bindings>,
+    user: HashMap<String, Bindings>,
+    comments: HashMap<String, Bindings>,
+    reader: HashMap<String, Bindings>,
+    saved: HashMap<String, Bindings>,
+}
+
+impl KeySettings {
+    /// Overrides configured for one mode
+    fn section(&self, mode: Mode) -> &HashMap<String, Bindings> {
+        match mode {
+            Mode::Stories => &self.stories,
+            Mode::Search => &self.search,
+            Mode::User => &self.user,
+            Mode::Comments => &self.comments,
+            Mode::Reader => &self.reader,
+            Mode::Saved => &self.saved,
+        }
+    }
+}
+
+/// Bindings of one mode
+#[derive(Debug, Clone, Default)]
+struct Layer {
+    /// Key sequences in definition order
+    bindings: Vec<(Sequence, Action)>,
+}
+
+impl Layer {
+    /// Replaces all bindings of `action` with `sequences`. A sequence already bound
+    /// to another action moves to this one.
+    fn rebind(&mut self, action: Action, sequences: Vec<Sequence>) {
+        self.bindings
+            .retain(|(keys, bound)| *bound != action && !sequences.contains(keys));
+        self.bindings
+            .extend(sequences.into_iter().map(|keys| (keys, action)));
+    }
+
+    /// Rejects a sequence that could never fire because another one starts with it
+    fn check_prefixes(&self) -> Result<()> {
+        for (keys, action) in &self.bindings {
+            for (other, other_action) in &self.bindings {
+                if other.len() > keys.len() && other.starts_with(keys) {
+                    bail!(
+                        "\"{}\" ({}) is the start of \"{}\" ({})",
+                        format_sequence(keys),
+                        action.name(),
+                        format_sequence(other),
+                        other_action.name()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Press {
+    /// A binding completed
+    Action(Action),
+    /// The key started or continued a longer sequence
+    Pending,
+    /// The key is not bound
+    Unbound,
+}
+
+/// Key bindings of every mode, plus the keys typed so far of an unfinished sequence
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    layers: HashMap<Mode, Layer>,
+    /// Keys typed so far towards a multi-key sequence, and the mode they were typed in
+    pending: Option<(Mode, Sequence)>,
+}
+
+impl Keymap {
+    /// Builds the keymap from the defaults and the configured overrides
+    pub fn new(settings: &KeySettings) -> Result<Self> {
+        let mut layers = HashMap::new();
+        for mode in Mode::ALL {
+            let mut layer = Layer::default();
+            for &(keys, action) in mode.defaults() {
+                let keys = parse_sequence(keys)
+                    .with_context(|| format!("invalid default binding \"{}\"", keys))?;
+                layer.bindings.push((keys, action));
+            }
+
+            // Screen-wide overrides only touch screens that have the action
+            for (name, bindings) in sorted(&settings.all) {
+                let action = action(name).context("keys.all")?;
+                if layer.bindings.iter().any(|(_, bound)| *bound == action) {
+                    let sequences =
+                        sequences(bindings).with_context(|| format!("keys.all.{}", name))?;
+                    layer.rebind(action, sequences);
+                }
+            }
+            for (name, bindings) in sorted(settings.section(mode)) {
+                let section = format!("keys.{}", mode.name());
+                let action = action(name).with_context(|| section.clone())?;
+                if !mode.defaults().iter().any(|&(_, bound)| bound == action) {
+                    bail!("{}: {} is not available on this screen", section, name);
+                }
+                let sequences =
+                    sequences(bindings).with_context(|| format!("{}.{}", section, name))?;
+                layer.rebind(action, sequences);
+            }
+
+            layer
+                .check_prefixes()
+                .with_context(|| format!("keys.{}", mode.name()))?;
+            layers.insert(mode, layer);
+        }
+        Ok(Self {
+            layers,
+            pending: None,
+        })
+    }
+
+    /// Feeds a key press typed in `mode`
+    pub fn press(&mut self, mode: Mode, event: KeyEvent) -> Press {
+        let chord = KeyChord::from_event(event);
+        let mut typed = match self.pending.take() {
+            Some((pending_mode, keys)) if pending_mode == mode => keys,
+            _ => Vec::new(),
+        };
+        typed.push(chord);
+
+        let mut press = self.lookup(mode, &typed);
+        if press == Press::Unbound && typed.len() > 1 {
+            // A key that breaks off a sequence starts over on its own
+            typed = vec![chord];
+            press = self.lookup(mode, &typed);
+        }
+        if press == Press::Pending {
+            self.pending = Some((mode, typed));
+        }
+        press
+    }
+
+    /// Every action available in a mode with the keys currently bound to it, in
+    /// the order of the built-in bindings
+    pub fn bindings(&self, mode: Mode) -> Vec<(Action, Vec<String>)> {
+        let mut entries: Vec<(Action, Vec<String>)> = Vec::new();
+        for &(_, action) in mode.defaults() {
+            if entries.iter().all(|(listed, _)| *listed != action) {
+                entries.push((action, Vec::new()));
+            }
+        }
+        if let Some(layer) = self.layers.get(&mode) {
+            for (keys, action) in &layer.bindings {
+                if let Some((_, bound)) = entries.iter_mut().find(|(listed, _)| listed == action) {
+                    bound.push(format_sequence(keys));
+                }
+            }
+        }
+        entries
+    }
+
+    /// Resolves typed keys against a mode's bindings
+    fn lookup(&self, mode: Mode, typed: &[KeyChord]) -> Press {
+        let Some(layer) = self.layers.get(&mode) else {
+            return Press::Unbound;
+        };
+        let mut pending = false;
+        for (keys, action) in &layer.bindings {
+            if keys.as_slice() == typed {
+                return Press::Action(*action);
+            }
+            pending |= keys.starts_with(typed);
+        }
+        if pending {
+            Press::Pending
+        } else {
+            Press::Unbound
+        }
+    }
+}
+
+/// Config entries in a stable order, so errors do not depend on hash order
+fn sorted(section: &HashMap<String, Bindings>) -> Vec<(&String, &Bindings)> {
+    let mut entries: Vec<_> = section.iter().collect();
+    entries.sort_by_key(|(name, _)| *name);
+    entries
+}
+
+/// Looks up a configured action name
+fn action(name: &str) -> Result<Action> {
+    Action::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Action::ALL.iter().map(|action| action.name()).collect();
+        anyhow!(
+            "unknown action \"{}\", expected one of {}",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+/// Parses the configured key sequences of an action
+fn sequences(bindings: &Bindings) -> Result<Vec<Sequence>> {
+    bindings
+        .sequences()
+        .iter()
+        .map(|keys| parse_sequence(keys))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord { code, modifiers }
+    }
+
+    fn keymap(config: &str) -> Result<Keymap> {
+        Keymap::new(&toml::from_str(config).unwrap())
+    }
+
+    /// Presses keys written as a binding, returning the outcome of the last one
+    fn press(keymap: &mut Keymap, mode: Mode, keys: &str) -> Press {
+        let mut press = Press::Unbound;
+        for key in parse_sequence(keys).unwrap() {
+            press = keymap.press(mode, KeyEvent::new(key.code, key.modifiers));
+        }
+        press
+    }
+
+    #[test]
+    fn parses_keys() {
+        let ctrl_x = chord(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(KeyChord::parse("ctrl-x").unwrap(), ctrl_x);
+        assert_eq!(KeyChord::parse("C-x").unwrap(), ctrl_x);
+        assert_eq!(ctrl_x.to_string(), "ctrl-x");
+
+        let upper_g = chord(KeyCode::Char('G'), KeyModifiers::NONE);
+        assert_eq!(KeyChord::parse("G").unwrap(), upper_g);
+        assert_eq!(KeyChord::parse("shift-g").unwrap(), upper_g);
+        assert_eq!(
+            KeyChord::parse("space").unwrap(),
+            chord(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::parse("M-Left").unwrap(),
+            chord(KeyCode::Left, KeyModifiers::ALT)
+        );
+        assert_eq!(
+            KeyChord::parse("shift-tab").unwrap(),
+            chord(KeyCode::BackTab, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::parse("f5").unwrap(),
+            chord(KeyCode::F(5), KeyModifiers::NONE)
+        );
+
+        for bad in ["spaceship", "ctrl-", "f13", "hyper-x"] {
+            let error = KeyChord::parse(bad).unwrap_err();
+            assert_eq!(error.to_string(), format!("unknown key \"{}\"", bad));
+        }
+    }
+
+    #[test]
+    fn parses_sequences() {
+        assert_eq!(
+            parse_sequence(" ctrl-x   ctrl-c ").unwrap(),
+            vec![
+                chord(KeyCode::Char('x'), KeyModifiers::CONTROL),
+                chord(KeyCode::Char('c'), KeyModifiers::CONTROL)
+            ]
+        );
+        assert_eq!(
+            parse_sequence("  ").unwrap_err().to_string(),
+            "empty key binding"
+        );
+        assert!(parse_sequence("g bogus").is_err());
+    }
+
+    #[test]
+    fn defaults_include_sequences() {
+        let mut keymap = keymap("").unwrap();
+        assert_eq!(
+            press(&mut keymap, Mode::Stories, "j"),
+            Press::Action(Action::Down)
+        );
+        assert_eq!(press(&mut keymap, Mode::Stories, "g"), Press::Pending);
+        assert_eq!(
+            press(&mut keymap, Mode::Stories, "g"),
+            Press::Action(Action::Top)
+        );
+        // A key that breaks off a sequence counts on its own
+        assert_eq!(press(&mut keymap, Mode::Stories, "g"), Press::Pending);
+        assert_eq!(
+            press(&mut keymap, Mode::Stories, "k"),
+            Press::Action(Action::Up)
+        );
+        assert_eq!(press(&mut keymap, Mode::Stories, "ctrl-z"), Press::Unbound);
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults() {
+        let mut keymap = keymap(
+            r#"
+            [all]
+            down = ["ctrl-n", "down"]
+
+            [stories]
+            quit = "ctrl-x ctrl-c"
+            refresh = "j"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(press(&mut keymap, Mode::Stories, "q"), Press::Unbound);
+        assert_eq!(press(&mut keymap, Mode::Stories, "ctrl-x"), Press::Pending);
+        assert_eq!(
+            press(&mut keymap, Mode::Stories, "ctrl-c"),
+            Press::Action(Action::Quit)
+        );
+        // A key taken from another action moves to the new one
+        assert_eq!(
+            press(&mut keymap, Mode::Stories, "j"),
+            Press::Action(Action::Refresh)
+        );
+        assert_eq!(press(&mut keymap, Mode::Stories, "r"), Press::Unbound);
+        // [all] applies to every screen, but per-screen sections to theirs only
+        assert_eq!(press(&mut keymap, Mode::Comments, "j"), Press::Unbound);
+        assert_eq!(
+            press(&mut keymap, Mode::Comments, "ctrl-n"),
+            Press::Action(Action::Down)
+        );
+        assert_eq!(
+            press(&mut keymap, Mode::Comments, "q"),
+            Press::Action(Action::Back)
+        );
+
+        let bindings = keymap.bindings(Mode::Stories);
+        let keys = |action| {
+            bindings
+                .iter()
+                .find(|(bound, _)| *bound == action)
+                .map(|(_, keys)| keys.clone())
+                .unwrap()
+        };
+        assert_eq!(keys(Action::Quit), vec!["ctrl-x ctrl-c"]);
+        assert_eq!(keys(Action::Down), vec!["ctrl-n", "↓"]);
+        assert_eq!(keys(Action::Refresh), vec!["j"]);
+    }
+
+    #[test]
+    fn conflicting_prefixes_are_rejected() {
+        let error = keymap("[stories]\nquit = \"g\"").unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "keys.stories: \"g\" (quit) is the start of \"g g\" (top)"
+        );
+        // The conflict goes only once no sequence starts with the key
+        assert!(keymap("[stories]\nquit = \"g\"\ntop = \"g t\"").is_err());
+        assert!(keymap("[stories]\nquit = \"g\"\ntop = \"home\"").is_ok());
+    }
+
+    #[test]
+    fn bad_overrides_are_reported() {
+        let error = |config| format!("{:#}", keymap(config).unwrap_err());
+        assert!(error("[stories]\nquit = \"hyper-q\"").contains("unknown key \"hyper-q\""));
+        assert!(error("[stories]\nfly = \"f\"").starts_with("keys.stories: unknown action \"fly\""));
+        assert_eq!(
+            error("[reader]\nsearch = \"f\""),
+            "keys.reader: search is not available on this screen"
+        );
+        assert!(toml::from_str::<KeySettings>("[error]\nquit = \"q\"").is_err());
+    }
+}
@@ -0,0 +1,333 @@
+//! Killfile.
+//!
+//! Hides stories from the feeds by domain, author, title pattern or low score and
+//! comment counts. Rules come from the `[filters]` section of the config file,
+//! plus the domains and users hidden from the story list with a key, which are
+//! kept in `killfile.json` under the XDG data directory.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{Item, ItemType};
+
+/// The `[filters]` config section
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterSettings {
+    /// Domains such as `example.com` (subdomains included) or globs such as
+    /// `*.substack.*`
+    pub domains: Vec<String>,
+    /// Usernames whose stories are hidden
+    pub users: Vec<String>,
+    /// Regular expressions matched against titles, ignoring case
+    pub titles: Vec<String>,
+    /// Stories with fewer points are hidden
+    pub min_score: u32,
+    /// Stories with fewer comments are hidden
+    pub min_comments: u32,
+}
+
+/// Why a story is hidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    Domain,
+    User,
+    Title,
+    Score,
+    Comments,
+}
+
+impl Reason {
+    /// Short label shown on a hidden story while hidden stories are revealed
+    pub fn label(self) -> &'static str {
+        match self {
+            Reason::Domain => "domain",
+            Reason::User => "user",
+            Reason::Title => "title",
+            Reason::Score => "score",
+            Reason::Comments => "comments",
+        }
+    }
+}
+
+/// Domains and users hidden with a key, as written to disk
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct Added {
+    domains: Vec<String>,
+    users: Vec<String>,
+}
+
+/// The configured rules together with the ones added from the story list
+#[derive(Debug)]
+pub struct Killfile {
+    /// File the added rules are written to, or `None` to keep them in memory only
+    path: Option<PathBuf>,
+    /// Lowercase domain patterns from the config
+    domains: Vec<String>,
+    /// Lowercase usernames from the config
+    users: Vec<String>,
+    titles: Vec<Regex>,
+    min_score: u32,
+    min_comments: u32,
+    added: Added,
+}
+
+impl Killfile {
+    /// Builds a killfile from the config alone, keeping added rules in memory.
+    ///
+    /// Fails if a title pattern is not a valid regular expression.
+    pub fn in_memory(settings: &FilterSettings) -> Result<Self> {
+        let titles = settings
+            .titles
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("filters.titles: invalid pattern \"{}\"", pattern))
+            })
+            .collect::<Result<_>>()?;
+        let lowercase = |values: &[String]| values.iter().map(|v| v.to_lowercase()).collect();
+        Ok(Self {
+            path: None,
+            domains: lowercase(&settings.domains),
+            users: lowercase(&settings.users),
+            titles,
+            min_score: settings.min_score,
+            min_comments: settings.min_comments,
+            added: Added::default(),
+        })
+    }
+
+    /// Builds a killfile from the config and the rules added in earlier sessions,
+    /// stored at the default location
+    pub fn open(settings: &FilterSettings) -> Result<Self> {
+        let mut killfile = Self::in_memory(settings)?;
+        let Some(path) = dirs::data_dir().map(|dir| dir.join("hn").join("killfile.json")) else {
+            return Ok(killfile);
+        };
+        killfile.added = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Invalid killfile {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Added::default(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Cannot read killfile {}", path.display()))
+            }
+        };
+        killfile.path = Some(path);
+        Ok(killfile)
+    }
+
+    /// Why an item is hidden, or `None` if it is shown
+    pub fn check(&self, item: &Item) -> Option<Reason> {
+        if let Some(domain) = item.domain() {
+            let domain = domain.to_lowercase();
+            let mut patterns = self.domains.iter().chain(&self.added.domains);
+            if patterns.any(|pattern| domain_matches(pattern, &domain)) {
+                return Some(Reason::Domain);
+            }
+        }
+        if let Some(author) = &item.by {
+            let author = author.to_lowercase();
+            let mut users = self.users.iter().chain(&self.added.users);
+            if users.any(|user| *user == author) {
+                return Some(Reason::User);
+            }
+        }
+        if let Some(title) = &item.title {
+            if self.titles.iter().any(|regex| regex.is_match(title)) {
+                return Some(Reason::Title);
+            }
+        }
+        // Thresholds only apply to kinds that collect votes and comments
+        if matches!(item.kind, ItemType::Story | ItemType::Poll) {
+            if item.score.unwrap_or(0) < self.min_score {
+                return Some(Reason::Score);
+            }
+            if item.descendants.unwrap_or(0) < self.min_comments {
+                return Some(Reason::Comments);
+            }
+        }
+        None
+    }
+
+    /// Hides every story from a domain and its subdomains from now on
+    pub fn hide_domain(&mut self, domain: &str) -> Result<()> {
+        let domain = domain.to_lowercase();
+        let domain = domain.strip_prefix("www.").unwrap_or(&domain).to_string();
+        if !self.added.domains.contains(&domain) {
+            self.added.domains.push(domain);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Hides every story by a user from now on
+    pub fn hide_user(&mut self, user: &str) -> Result<()> {
+        let user = user.to_lowercase();
+        if !self.added.users.contains(&user) {
+            self.added.users.push(user);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the added rules
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        write(path, &self.added)
+            .with_context(|| format!("Cannot save killfile to {}", path.display()))
+    }
+}
+
+/// Whether a lowercase domain matches a pattern. Patterns with `*` or `?` are
+/// globs over the whole domain; others match the domain and its subdomains.
+fn domain_matches(pattern: &str, domain: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        return glob_matches(pattern, domain);
+    }
+    domain == pattern
+        || domain
+            .strip_suffix(pattern)
+            .is_some_and(|rest| rest.ends_with('.'))
+}
+
+/// Matches `text` against a glob where `*` is any run of characters and `?` any
+/// single character
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it is trying
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character
+                Some((after, from)) => {
+                    star = Some((after, from + 1));
+                    p = after;
+                    t = from + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Serializes the added rules to `path` through a temporary file
+fn write(path: &Path, added: &Added) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let data = serde_json::to_vec_pretty(added)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(url: &str, by: &str, title: &str, score: u32, comments: u32) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "id": 1, "type": "story", "time": 0, "url": url, "by": by, "title": title,
+            "score": score, "descendants": comments
+        }))
+        .unwrap()
+    }
+
+    fn killfile(config: &str) -> Killfile {
+        Killfile::in_memory(&toml::from_str(config).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn plain_domains_include_subdomains_only() {
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(domain_matches("example.com", "blog.example.com"));
+        assert!(!domain_matches("example.com", "notexample.com"));
+        assert!(!domain_matches("example.com", "example.com.evil.org"));
+        assert!(!domain_matches("blog.example.com", "example.com"));
+    }
+
+    #[test]
+    fn globs_match_the_whole_domain() {
+        assert!(domain_matches("*.example.com", "blog.example.com"));
+        assert!(domain_matches("*.example.com", "a.b.example.com"));
+        assert!(!domain_matches("*.example.com", "example.com"));
+        assert!(!domain_matches("*.example.com", "notexample.com"));
+        assert!(domain_matches("*.substack.*", "someone.substack.com"));
+        assert!(domain_matches("example.c?m", "example.com"));
+        assert!(!domain_matches("example.c?m", "example.cm"));
+        // `?` is one character, however many bytes it takes
+        assert!(domain_matches("b?cher.de", "bücher.de"));
+        assert!(domain_matches("?.example.com", "ü.example.com"));
+        assert!(!domain_matches("??.example.com", "ü.example.com"));
+    }
+
+    #[test]
+    fn rules_report_why_a_story_is_hidden() {
+        let killfile = killfile(
+            r#"
+            domains = ["Example.com"]
+            users = ["Spammer"]
+            titles = ["^ask hn: .*crypto"]
+            "#,
+        );
+        let check = |url, by, title| killfile.check(&story(url, by, title, 10, 10));
+        assert_eq!(
+            check("https://www.example.com/a", "pg", "Hi"),
+            Some(Reason::Domain)
+        );
+        assert_eq!(check("https://notexample.com/a", "pg", "Hi"), None);
+        assert_eq!(check("https://a.org", "spammer", "Hi"), Some(Reason::User));
+        assert_eq!(
+            check("https://a.org", "pg", "Ask HN: Is Crypto over?"),
+            Some(Reason::Title)
+        );
+        assert_eq!(check("https://a.org", "pg", "Crypto, asked"), None);
+    }
+
+    #[test]
+    fn thresholds_apply_to_stories_and_polls() {
+        let killfile = killfile("min_score = 10\nmin_comments = 2");
+        let check = |score, comments| killfile.check(&story("", "pg", "Hi", score, comments));
+        assert_eq!(check(10, 2), None);
+        assert_eq!(check(9, 2), Some(Reason::Score));
+        assert_eq!(check(10, 1), Some(Reason::Comments));
+
+        let mut job = story("", "pg", "Hiring", 0, 0);
+        job.kind = ItemType::Job;
+        assert_eq!(killfile.check(&job), None);
+    }
+
+    #[test]
+    fn hidden_domains_and_users_apply_at_once() {
+        let mut killfile = killfile("");
+        let item = story("https://www.blog.example.com/a", "Someone", "Hi", 1, 0);
+        assert_eq!(killfile.check(&item), None);
+
+        killfile.hide_user("Someone").unwrap();
+        assert_eq!(killfile.check(&item), Some(Reason::User));
+        killfile.hide_domain("www.Example.com").unwrap();
+        assert_eq!(killfile.check(&item), Some(Reason::Domain));
+        assert_eq!(killfile.added.domains, vec!["example.com"]);
+    }
+}
@@ -0,0 +1,311 @@
+//! Live updates through the Firebase streaming API.
+//!
+//! Every Firebase URL can be requested with `Accept: text/event-stream`, in which
+//! case the server answers with a `put` event carrying the whole document and then
+//! `put`/`patch` events describing each change under a path. [`Document`] replays
+//! those events so callers always see the current value.
+
+use anyhow::{bail, Result};
+use reqwest::Response;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A change reported by a live subscription
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiveEvent {
+    /// The stream is connected and changes arrive as they happen
+    Streaming,
+    /// Streaming is unavailable and the feed is polled instead
+    Polling,
+    /// The feed's ranked id list changed
+    FeedIds(Vec<u64>),
+    /// These items changed somewhere on Hacker News
+    ItemsChanged(Vec<u64>),
+    /// A poll interval elapsed and the visible items should be refetched
+    Tick,
+}
+
+/// Payload of `put` and `patch` events
+#[derive(Deserialize, Debug)]
+struct Change {
+    path: String,
+    data: Value,
+}
+
+/// A JSON document kept in sync with a Firebase event stream
+#[derive(Debug, Default)]
+struct Document {
+    /// Current value of the document
+    value: Value,
+}
+
+impl Document {
+    /// Applies one event, returning true if the document changed
+    fn apply(&mut self, event: &str, data: &str) -> Result<bool> {
+        match event {
+            "put" => {
+                let change: Change = serde_json::from_str(data)?;
+                *self.node(&change.path) = change.data;
+                Ok(true)
+            }
+            "patch" => {
+                let change: Change = serde_json::from_str(data)?;
+                let Value::Object(fields) = change.data else {
+                    bail!("patch data is not an object");
+                };
+                for (key, value) in fields {
+                    *self.node(&format!("{}/{}", change.path, key)) = value;
+                }
+                Ok(true)
+            }
+            "cancel" | "auth_revoked" => bail!("stream closed by server ({})", event),
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns the node at a `/`-separated path, creating it if missing
+    fn node(&mut self, path: &str) -> &mut Value {
+        let mut node = &mut self.value;
+        for key in path.split('/').filter(|key| !key.is_empty()) {
+            let index = key.parse::<usize>().ok();
+            if !matches!(
+                (&*node, index),
+                (Value::Array(_), Some(_)) | (Value::Object(_), _)
+            ) {
+                *node = Value::Object(Default::default());
+            }
+            node = match (node, index) {
+                (Value::Array(values), Some(index)) => {
+                    if values.len() <= index {
+                        values.resize(index + 1, Value::Null);
+                    }
+                    &mut values[index]
+                }
+                (node, _) => &mut node[key],
+            };
+        }
+        node
+    }
+}
+
+/// Extracts the ids of a Firebase list, which arrives as an array or, once patched
+/// sparsely, as an object keyed by index
+pub fn ids(value: &Value) -> Vec<u64> {
+    match value {
+        Value::Array(values) => values.iter().filter_map(Value::as_u64).collect(),
+        Value::Object(fields) => {
+            let mut entries: Vec<(usize, u64)> = fields
+                .iter()
+                .filter_map(|(key, value)| Some((key.parse().ok()?, value.as_u64()?)))
+                .collect();
+            entries.sort_unstable();
+            entries.into_iter().map(|(_, id)| id).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Reads an event stream until it ends, calling `on_change` with the document after
+/// every change
+pub async fn read_stream<F>(mut response: Response, on_change: F) -> Result<()>
+where
+    F: Fn(&Value),
+{
+    let mut parser = Parser::default();
+    let mut document = Document::default();
+    while let Some(chunk) = response.chunk().await? {
+        for (event, data) in parser.push(&chunk) {
+            if document.apply(&event, &data)? {
+                on_change(&document.value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Incremental parser for `text/event-stream` bodies
+#[derive(Debug, Default)]
+struct Parser {
+    /// Bytes of the current, incomplete line
+    line: Vec<u8>,
+    /// Name of the event being assembled
+    event: String,
+    /// Data lines of the event being assembled
+    data: Vec<String>,
+}
+
+impl Parser {
+    /// Feeds a chunk of the body and returns the `(event, data)` pairs it completed
+    fn push(&mut self, chunk: &[u8]) -> Vec<(String, String)> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let line = String::from_utf8_lossy(&self.line)
+                .trim_end_matches('\r')
+                .to_string();
+            self.line.clear();
+
+            if line.is_empty() {
+                // A blank line dispatches the event
+                if !self.event.is_empty() || !self.data.is_empty() {
+                    events.push((std::mem::take(&mut self.event), self.data.join("\n")));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("event:") {
+                self.event = value.trim_start().to_string();
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data
+                    .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A recorded `topstories` stream: the initial document, a keep-alive, a rank
+    /// change and a sparse patch
+    const RECORDED: &str = "event: put\n\
+        data: {\"path\":\"/\",\"data\":[101,102,103]}\n\
+        \n\
+        event: keep-alive\n\
+        data: null\n\
+        \n\
+        event: put\n\
+        data: {\"path\":\"/1\",\"data\":104}\n\
+        \n\
+        event: patch\n\
+        data: {\"path\":\"/\",\"data\":{\"0\":105,\"3\":102}}\n\
+        \n";
+
+    /// Replays a stream delivered in the given chunks, returning the document after
+    /// every change
+    fn replay(chunks: &[&[u8]]) -> Vec<Value> {
+        let mut parser = Parser::default();
+        let mut document = Document::default();
+        let mut snapshots = Vec::new();
+        for chunk in chunks {
+            for (event, data) in parser.push(chunk) {
+                if document.apply(&event, &data).unwrap() {
+                    snapshots.push(document.value.clone());
+                }
+            }
+        }
+        snapshots
+    }
+
+    #[test]
+    fn parses_complete_events() {
+        let mut parser = Parser::default();
+        let events = parser.push(RECORDED.as_bytes());
+        let names: Vec<&str> = events.iter().map(|(event, _)| event.as_str()).collect();
+        assert_eq!(names, ["put", "keep-alive", "put", "patch"]);
+        assert_eq!(events[1].1, "null");
+    }
+
+    #[test]
+    fn replays_recorded_stream() {
+        let snapshots = replay(&[RECORDED.as_bytes()]);
+        assert_eq!(
+            snapshots,
+            [
+                json!([101, 102, 103]),
+                json!([101, 104, 103]),
+                json!([105, 104, 103, 102]),
+            ]
+        );
+        assert_eq!(ids(snapshots.last().unwrap()), [105, 104, 103, 102]);
+    }
+
+    #[test]
+    fn events_split_across_chunks() {
+        let bytes = RECORDED.as_bytes();
+        let whole = replay(&[bytes]);
+        // Every split point, including inside a line or a field name
+        for split in 0..bytes.len() {
+            assert_eq!(
+                replay(&[&bytes[..split], &bytes[split..]]),
+                whole,
+                "{}",
+                split
+            );
+        }
+        // One byte at a time
+        let bytes: Vec<&[u8]> = bytes.chunks(1).collect();
+        assert_eq!(replay(&bytes), whole);
+    }
+
+    #[test]
+    fn handles_crlf_and_multiline_data() {
+        let mut parser = Parser::default();
+        let events =
+            parser.push(b"event: put\r\ndata: {\"path\":\"/\",\r\ndata: \"data\":1}\r\n\r\n");
+        assert_eq!(
+            events,
+            [(
+                "put".to_string(),
+                "{\"path\":\"/\",\n\"data\":1}".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn incomplete_event_waits_for_blank_line() {
+        let mut parser = Parser::default();
+        assert!(parser.push(b"event: put\ndata: {}\n").is_empty());
+        assert_eq!(parser.push(b"\n").len(), 1);
+        // Stray blank lines and comments dispatch nothing
+        assert!(parser.push(b"\n\n: comment\n\n").is_empty());
+    }
+
+    #[test]
+    fn put_and_patch_at_nested_paths() {
+        let mut document = Document::default();
+        document
+            .apply("put", r#"{"path":"/","data":{"items":[1,2]}}"#)
+            .unwrap();
+        document
+            .apply("put", r#"{"path":"/items/2","data":3}"#)
+            .unwrap();
+        document
+            .apply("patch", r#"{"path":"/profiles","data":{"pg":1}}"#)
+            .unwrap();
+        assert_eq!(
+            document.value,
+            json!({"items": [1, 2, 3], "profiles": {"pg": 1}})
+        );
+        assert_eq!(ids(&document.value["items"]), [1, 2, 3]);
+
+        // A put replacing the root drops everything else
+        document
+            .apply("put", r#"{"path":"/","data":null}"#)
+            .unwrap();
+        assert_eq!(document.value, Value::Null);
+    }
+
+    #[test]
+    fn sparse_lists_keep_index_order() {
+        assert_eq!(ids(&json!({"10": 3, "2": 1, "x": 9, "5": 2})), [1, 2, 3]);
+        assert!(ids(&json!("not a list")).is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_events() {
+        let mut document = Document::default();
+        assert!(!document.apply("keep-alive", "null").unwrap());
+        assert!(document.apply("put", "not json").is_err());
+        assert!(document
+            .apply("patch", r#"{"path":"/","data":[1]}"#)
+            .is_err());
+        assert!(document.apply("cancel", "null").is_err());
+        assert!(document.apply("auth_revoked", "null").is_err());
+    }
+}
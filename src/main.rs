@@ -1,387 +1,3618 @@
+mod bookmarks;
+mod cache;
+mod client;
+mod config;
+mod filter;
+mod history;
+mod keymap;
+mod killfile;
+mod live;
+mod markup;
+mod palette;
+mod reader;
+mod search;
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
 
-use anyhow::Result;
-use reqwest::Client;
-use serde::Deserialize;
+use bookmarks::Bookmarks;
+use cache::{Cache, Freshness};
+use client::{CoalescingClient, FixtureClient, HnClient, HttpClient, DEFAULT_API_URL};
+use config::{Config, Theme, UiSettings};
+use history::{History, Snapshot};
+use keymap::{Action, Keymap, Mode, Press};
+use killfile::{Killfile, Reason};
+use live::LiveEvent;
+use markup::RichText;
+use palette::Palette;
+use reader::Article;
+use search::{SearchPage, SearchQuery, DEFAULT_SEARCH_URL};
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs, Wrap},
     Frame, Terminal,
 };
 
+/// Start fetching the next page when the selection is this close to the end
+const PREFETCH_MARGIN: usize = 5;
+
+/// How often the feed is polled when live streaming is unavailable
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Delay before reconnecting a live stream that dropped
+const LIVE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Number of stories around the selection that live updates keep current
+const LIVE_WINDOW: usize = 30;
+
+/// How long a live change stays highlighted in the story list
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(30);
+
+/// Story lists at least this wide get a detail pane beside them
+const SPLIT_MIN_WIDTH: u16 = 120;
+
+/// Number of top-level comments previewed in the detail pane
+const DETAIL_COMMENTS: usize = 5;
+
+/// Two clicks on the same row within this interval open it
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Lines the reader scrolls per mouse wheel notch
+const WHEEL_LINES: usize = 3;
+
+/// How long a notice replaces the footer
+const NOTICE_DURATION: Duration = Duration::from_secs(4);
+
+/// Frames of the spinner shown while a feed loads, one per 100ms
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Style laid over the text matching a list filter
+const MATCH_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+
 // ===== DATA STRUCTURES =====
 
-/// Represents a Hacker News story item
-#[derive(Deserialize, Debug, Clone)]
+/// Kind of a Hacker News item
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum ItemType {
+    #[default]
+    Story,
+    Comment,
+    Job,
+    Poll,
+    PollOpt,
+    /// Any type this client does not know about
+    #[serde(other)]
+    Unknown,
+}
+
+/// Represents a Hacker News item of any type.
+///
+/// Every field except `id` may be missing: deleted items carry little more than
+/// their id, jobs have no score and comments have no title.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct Item {
-    /// Title of the story
-    title: String,
+    /// Unique item id
+    id: u64,
+    /// Kind of item (story, comment, job, poll or poll option)
+    #[serde(rename = "type", default)]
+    kind: ItemType,
+    /// Whether the item has been deleted
+    #[serde(default)]
+    deleted: bool,
+    /// Whether the item has been flagged dead
+    #[serde(default)]
+    dead: bool,
+    /// Title of a story, job or poll
+    #[serde(default)]
+    title: Option<String>,
     /// Optional URL to the original article
     #[serde(default)]
     url: Option<String>,
-    /// Number of upvotes the story has received
-    score: u32,
-    /// Username of the person who submitted the story
-    by: String,
-    /// Unix timestamp when the story was submitted
+    /// Number of upvotes (votes for poll options)
+    #[serde(default)]
+    score: Option<u32>,
+    /// Username of the author
+    #[serde(default)]
+    by: Option<String>,
+    /// Unix timestamp when the item was created
+    #[serde(default)]
     time: u64,
-    /// Optional number of comments on the story
+    /// Optional number of comments on a story or poll
     #[serde(default)]
     descendants: Option<u32>,
-    /// Optional story text content (for Show HN posts)
+    /// Optional text content (Ask/Show HN posts, jobs and comments, as HTML)
     #[serde(default)]
     text: Option<String>,
+    /// Ids of direct replies, in ranked order
+    #[serde(default)]
+    kids: Vec<u64>,
+    /// Id of the parent comment or story (comments only)
+    #[serde(default)]
+    parent: Option<u64>,
+    /// Ids of the poll's options, in display order (polls only)
+    #[serde(default)]
+    parts: Vec<u64>,
+    /// Id of the poll an option belongs to (poll options only)
+    #[serde(default)]
+    poll: Option<u64>,
 }
 
-/// Application state enum to handle different screens
-#[derive(Debug, PartialEq)]
-enum AppState {
-    Loading,
-    Stories,
-    ShowHN, // Show HN posts only
-    Error(String),
+impl Item {
+    /// Author's username, or a placeholder when it is missing
+    fn author(&self) -> &str {
+        self.by.as_deref().unwrap_or("[deleted]")
+    }
+
+    /// Title to show in lists, falling back to a text excerpt for untitled items
+    fn display_title(&self) -> String {
+        if self.deleted {
+            return "[deleted]".to_string();
+        }
+        if let Some(title) = &self.title {
+            return title.clone();
+        }
+        let text = markup::to_plain(&self.rich_text().unwrap_or_default());
+        let excerpt: String = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if excerpt.chars().count() > 80 {
+            format!("{}…", excerpt.chars().take(79).collect::<String>())
+        } else {
+            excerpt
+        }
+    }
+
+    /// The item's HTML text converted for display, if it has any
+    fn rich_text(&self) -> Option<RichText> {
+        self.text
+            .as_deref()
+            .filter(|text| !text.is_empty())
+            .map(markup::from_hn_html)
+    }
+
+    /// Domain of the item's URL, if it links anywhere
+    fn domain(&self) -> Option<&str> {
+        self.url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+            .map(|url| url.split('/').nth(2).unwrap_or("unknown"))
+    }
+
+    /// Short tag for kinds that are not plain stories
+    fn kind_tag(&self) -> Option<&'static str> {
+        match self.kind {
+            ItemType::Job => Some("[job] "),
+            ItemType::Poll => Some("[poll] "),
+            ItemType::Comment => Some("[comment] "),
+            ItemType::PollOpt => Some("[poll option] "),
+            ItemType::Story | ItemType::Unknown => None,
+        }
+    }
 }
 
-/// Main application state
-#[derive(Debug)]
-struct App {
-    /// List of stories to display
-    stories: Vec<Item>,
-    /// List of Show HN stories
-    show_stories: Vec<Item>,
-    /// Index of the currently selected story
+/// A Hacker News user profile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct User {
+    /// Case-sensitive username
+    id: String,
+    /// Unix timestamp when the account was created
+    #[serde(default)]
+    created: u64,
+    /// Account karma
+    #[serde(default)]
+    karma: i64,
+    /// Optional self-description (HTML)
+    #[serde(default)]
+    about: Option<String>,
+    /// Ids of the user's stories, polls and comments, newest first
+    #[serde(default)]
+    submitted: Vec<u64>,
+}
+
+/// A comment together with its fetched replies
+#[derive(Debug, Clone)]
+struct CommentNode {
+    item: Item,
+    replies: Vec<CommentNode>,
+}
+
+/// Items fetched by id, with a count of those that could not be loaded
+#[derive(Debug, Default)]
+struct Fetched {
+    /// Items that loaded, in the order of their ids
+    items: Vec<Item>,
+    /// Number of ids whose items failed to load
+    failed: usize,
+}
+
+/// A comment positioned in the flattened comment tree
+#[derive(Debug, Clone)]
+struct CommentEntry {
+    /// The comment itself
+    item: Item,
+    /// Nesting level (0 for top-level comments)
+    depth: usize,
+    /// Index of the parent entry, if any
+    parent: Option<usize>,
+    /// Number of entries in this comment's subtree (excluding itself)
+    descendants: usize,
+    /// The comment's text, converted once for display
+    text: RichText,
+}
+
+/// State of the threaded comment reader
+#[derive(Debug, Default)]
+struct CommentView {
+    /// Story whose discussion is shown
+    story: Option<Item>,
+    /// Comments flattened in pre-order, so children directly follow their parent
+    entries: Vec<CommentEntry>,
+    /// Indices of entries whose replies are hidden
+    collapsed: HashSet<usize>,
+    /// Index into `entries` of the selected comment
     selected: usize,
-    /// Index of the currently selected Show HN story
-    show_selected: usize,
-    /// Current application state
-    state: AppState,
-    /// Loading progress (0-100)
-    loading_progress: u16,
-    /// Whether to show detailed info (score, author, comments, time)
-    show_info: bool,
+    /// Options of the story, if it is a poll
+    poll_options: Vec<Item>,
+    /// Whether the comment tree is still being fetched
+    loading: bool,
+    /// Why the discussion could not be loaded
+    error: Option<String>,
+    /// Number of comments that failed to load
+    failed: usize,
 }
 
-// ===== APP IMPLEMENTATION =====
+/// Hacker News story feeds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+enum Feed {
+    Top,
+    New,
+    Best,
+    Ask,
+    Show,
+    Jobs,
+}
 
-impl App {
-    /// Creates a new App instance with default values
-    fn new() -> Self {
-        Self {
-            stories: Vec::new(),
-            show_stories: Vec::new(),
-            selected: 0,
-            show_selected: 0,
-            state: AppState::Loading,
-            loading_progress: 0,
-            show_info: false, // Default to off
+impl Feed {
+    /// All feeds in tab order
+    const ALL: [Feed; 6] = [
+        Feed::Top,
+        Feed::New,
+        Feed::Best,
+        Feed::Ask,
+        Feed::Show,
+        Feed::Jobs,
+    ];
+
+    /// Name of the feed's id list endpoint
+    fn endpoint(self) -> &'static str {
+        match self {
+            Feed::Top => "topstories",
+            Feed::New => "newstories",
+            Feed::Best => "beststories",
+            Feed::Ask => "askstories",
+            Feed::Show => "showstories",
+            Feed::Jobs => "jobstories",
         }
     }
 
-    /// Toggles the info display
-    fn toggle_info(&mut self) {
-        self.show_info = !self.show_info;
+    /// Name used for the feed in the config file
+    fn name(self) -> &'static str {
+        match self {
+            Feed::Top => "top",
+            Feed::New => "new",
+            Feed::Best => "best",
+            Feed::Ask => "ask",
+            Feed::Show => "show",
+            Feed::Jobs => "jobs",
+        }
     }
 
-    /// Switches to Show HN view
-    fn switch_to_show_hn(&mut self) {
-        self.state = AppState::ShowHN;
+    /// Short name shown in the tab bar
+    fn label(self) -> &'static str {
+        match self {
+            Feed::Top => "Top",
+            Feed::New => "New",
+            Feed::Best => "Best",
+            Feed::Ask => "Ask HN",
+            Feed::Show => "Show HN",
+            Feed::Jobs => "Jobs",
+        }
     }
 
-    /// Returns to the stories list
-    fn back_to_stories(&mut self) {
-        self.state = AppState::Stories;
+    /// Icon used in titles for this feed
+    fn icon(self) -> &'static str {
+        match self {
+            Feed::Top => "📰",
+            Feed::New => "🆕",
+            Feed::Best => "🏆",
+            Feed::Ask => "❓",
+            Feed::Show => "🚀",
+            Feed::Jobs => "💼",
+        }
     }
 
-    /// Sets the Show HN stories
-    fn set_show_stories(&mut self, stories: Vec<Item>) {
-        self.show_stories = stories;
-        self.show_selected = 0;
+    /// Title prefix HN adds to posts in this feed, rendered as a tag
+    fn title_prefix(self) -> Option<&'static str> {
+        match self {
+            Feed::Ask => Some("Ask HN: "),
+            Feed::Show => Some("Show HN: "),
+            _ => None,
+        }
     }
 
-    /// Moves selection to the next story if available
-    fn next(&mut self) {
-        match self.state {
-            AppState::Stories => {
-                if !self.stories.is_empty() && self.selected < self.stories.len().saturating_sub(1)
-                {
-                    self.selected += 1;
-                }
-            }
-            AppState::ShowHN => {
-                if !self.show_stories.is_empty()
-                    && self.show_selected < self.show_stories.len().saturating_sub(1)
-                {
-                    self.show_selected += 1;
-                }
-            }
-            _ => {}
+    /// Accent color for headers and borders
+    fn accent(self) -> Color {
+        match self {
+            Feed::Top => Color::Blue,
+            Feed::New => Color::Green,
+            Feed::Best => Color::Yellow,
+            Feed::Ask => Color::Cyan,
+            Feed::Show => Color::Magenta,
+            Feed::Jobs => Color::Red,
         }
     }
 
-    /// Moves selection to the previous story if available
-    fn previous(&mut self) {
-        match self.state {
-            AppState::Stories => {
-                if self.selected > 0 {
-                    self.selected -= 1;
-                }
+    /// Style of the selected row in the story list, unless the theme overrides it
+    fn highlight_style(self, theme: &Theme) -> Style {
+        let (bg, fg) = match self {
+            Feed::Top => (Color::Yellow, Color::Black),
+            Feed::Show | Feed::Jobs => (self.accent(), Color::White),
+            _ => (self.accent(), Color::Black),
+        };
+        Style::default()
+            .bg(theme.selection.unwrap_or(bg))
+            .fg(theme.selection_text.unwrap_or(fg))
+            .add_modifier(Modifier::BOLD)
+    }
+
+    /// Position of the feed in the tab bar
+    fn index(self) -> usize {
+        Feed::ALL.iter().position(|&f| f == self).unwrap_or(0)
+    }
+
+    /// The feed to the right in the tab bar, wrapping around
+    fn next(self) -> Feed {
+        Feed::ALL[(self.index() + 1) % Feed::ALL.len()]
+    }
+
+    /// The feed to the left in the tab bar, wrapping around
+    fn previous(self) -> Feed {
+        Feed::ALL[(self.index() + Feed::ALL.len() - 1) % Feed::ALL.len()]
+    }
+}
+
+/// Order of a story list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum SortMode {
+    /// The order the API ranks the stories in
+    #[default]
+    Rank,
+    /// Most points first
+    Score,
+    /// Most comments first
+    Comments,
+    /// Newest first
+    Age,
+    /// Most points per hour since posting first
+    Velocity,
+    /// Alphabetically by domain, stories without a link last
+    Domain,
+}
+
+impl SortMode {
+    /// All sort modes in the order the sort key cycles through them
+    const ALL: [SortMode; 6] = [
+        SortMode::Rank,
+        SortMode::Score,
+        SortMode::Comments,
+        SortMode::Age,
+        SortMode::Velocity,
+        SortMode::Domain,
+    ];
+
+    /// Name shown in the list title
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Rank => "rank",
+            SortMode::Score => "score",
+            SortMode::Comments => "comments",
+            SortMode::Age => "age",
+            SortMode::Velocity => "points/hour",
+            SortMode::Domain => "domain",
+        }
+    }
+
+    /// The mode after this one, wrapping around to rank order
+    fn next(self) -> SortMode {
+        let index = SortMode::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        SortMode::ALL[(index + 1) % SortMode::ALL.len()]
+    }
+
+    /// Sorts indexes into `stories`, which start in rank order. Ties keep their
+    /// rank order.
+    fn arrange(self, stories: &[Item], rows: &mut [usize]) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        // Stories younger than an hour count as an hour old, so a couple of
+        // early votes do not put a brand new story on top
+        let velocity = |story: &Item| {
+            let hours = (now.saturating_sub(story.time) as f64 / 3600.0).max(1.0);
+            f64::from(story.score.unwrap_or(0)) / hours
+        };
+        match self {
+            SortMode::Rank => {}
+            SortMode::Score => rows.sort_by_key(|&i| Reverse(stories[i].score.unwrap_or(0))),
+            SortMode::Comments => {
+                rows.sort_by_key(|&i| Reverse(stories[i].descendants.unwrap_or(0)))
             }
-            AppState::ShowHN => {
-                if self.show_selected > 0 {
-                    self.show_selected -= 1;
-                }
+            SortMode::Age => rows.sort_by_key(|&i| Reverse(stories[i].time)),
+            SortMode::Velocity => {
+                rows.sort_by(|&a, &b| velocity(&stories[b]).total_cmp(&velocity(&stories[a])))
             }
-            _ => {}
+            SortMode::Domain => rows.sort_by_key(|&i| {
+                let domain = stories[i].domain().map(|d| d.to_lowercase());
+                // Ignore "www." so www.example.com sorts with example.com
+                let domain =
+                    domain.map(|d| d.strip_prefix("www.").map(str::to_string).unwrap_or(d));
+                (domain.is_none(), domain)
+            }),
         }
     }
+}
 
-    /// Returns a reference to the currently selected story
-    fn selected_story(&self) -> Option<&Item> {
-        match self.state {
-            AppState::Stories => self.stories.get(self.selected),
-            AppState::ShowHN => self.show_stories.get(self.show_selected),
-            _ => None,
+/// What a live update changed about a story, kept for highlighting
+#[derive(Debug, Clone, Copy)]
+struct Change {
+    /// Whether the story just entered the loaded part of the feed
+    entered: bool,
+    /// Ranks gained (positive) or lost (negative)
+    moved: i64,
+    /// Points gained
+    score: i64,
+    /// Comments gained
+    comments: i64,
+    /// When the change was last updated
+    at: Instant,
+}
+
+impl Change {
+    /// Whether the change is recent enough to highlight
+    fn is_recent(&self) -> bool {
+        self.at.elapsed() < HIGHLIGHT_DURATION
+    }
+}
+
+/// A pageable story list: one of the feeds or a user's submissions.
+///
+/// Holds the loaded stories with their selection, scroll position, loading
+/// status, filter and order; `render_feed_list` draws any of them.
+#[derive(Debug, Default)]
+struct FeedView {
+    /// Feed the list shows, or `None` for a user's submissions
+    feed: Option<Feed>,
+    /// Full ranked id list returned by the feed endpoint
+    ids: Vec<u64>,
+    /// Loaded stories in ranked order
+    stories: Vec<Item>,
+    /// Number of ids from `ids` whose items have been requested
+    fetched: usize,
+    /// Index of the currently selected story
+    selected: usize,
+    /// First row scrolled into view, kept between frames
+    offset: usize,
+    /// Whether a fetch for this feed is in flight
+    loading: bool,
+    /// Progress of that fetch (0-100)
+    progress: u16,
+    /// Why the last fetch failed, shown above the list until the next one
+    error: Option<String>,
+    /// Number of loaded ids whose items failed to load
+    failed: usize,
+    /// Whether a further page of stories is being fetched
+    loading_more: bool,
+    /// Index in `ids` of the first story of that page
+    page_start: usize,
+    /// Number of the latest first-page fetch; results of earlier fetches are dropped
+    generation: u64,
+    /// Fetches of this list that may still be running, cancelled when it reloads
+    tasks: Vec<JoinHandle<()>>,
+    /// Recent live changes by story id
+    changes: HashMap<u64, Change>,
+    /// Ids of loaded stories the killfile hides
+    hidden: HashSet<u64>,
+    /// Text typed after `/` to narrow the list, empty when it is not filtered
+    filter: String,
+    /// Order the stories are shown in
+    sort: SortMode,
+}
+
+impl FeedView {
+    /// An empty list for a feed
+    fn for_feed(feed: Feed) -> Self {
+        Self {
+            feed: Some(feed),
+            ..Self::default()
         }
     }
 
-    /// Sets the stories and transitions to Stories state
-    fn set_stories(&mut self, stories: Vec<Item>) {
-        self.stories = stories;
-        self.state = AppState::Stories;
-        self.selected = 0;
+    /// Name shown in the list title
+    fn label(&self) -> &'static str {
+        self.feed.map_or("Submissions", Feed::label)
     }
 
-    /// Sets error state
-    fn set_error(&mut self, error: String) {
-        self.state = AppState::Error(error);
+    /// Icon shown in the list title
+    fn icon(&self) -> &'static str {
+        self.feed.map_or("📝", Feed::icon)
     }
 
-    /// Updates loading progress
-    fn update_loading_progress(&mut self, progress: u16) {
-        self.loading_progress = progress.min(100);
+    /// What the list holds, as used in status messages
+    fn description(&self) -> String {
+        match self.feed {
+            Some(feed) => format!("{} stories", feed.label()),
+            None => "submissions".to_string(),
+        }
     }
-}
 
-// ===== API FUNCTIONS =====
+    /// Accent color of the list
+    fn accent(&self) -> Color {
+        self.feed.map_or(Color::Magenta, Feed::accent)
+    }
 
-/// Fetches the top story IDs from Hacker News API
-async fn fetch_top_story_ids(client: &Client) -> Result<Vec<u64>> {
-    let url = "https://hacker-news.firebaseio.com/v0/topstories.json";
+    /// What one entry of the list is called
+    fn noun(&self) -> &'static str {
+        match self.feed {
+            Some(_) => "stories",
+            None => "submissions",
+        }
+    }
 
-    let response = client.get(url).send().await?;
-    let ids: Vec<u64> = response.json().await?;
+    /// Color of the list border. Top stories keep the theme's border, other
+    /// feeds use their accent.
+    fn border(&self, theme: &Theme) -> Color {
+        match self.feed {
+            Some(Feed::Top) => theme.border,
+            Some(feed) => feed.accent(),
+            None => Color::White,
+        }
+    }
 
-    Ok(ids.into_iter().take(30).collect())
-}
+    /// Style of the selected row
+    fn highlight_style(&self, theme: &Theme) -> Style {
+        match self.feed {
+            Some(feed) => feed.highlight_style(theme),
+            None => Style::default()
+                .bg(self.accent())
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
 
-/// Fetches the Show HN story IDs from Hacker News API
-async fn fetch_show_story_ids(client: &Client) -> Result<Vec<u64>> {
-    let url = "https://hacker-news.firebaseio.com/v0/showstories.json";
+    /// Whether ids remain whose stories have not been fetched yet
+    fn has_more(&self) -> bool {
+        self.fetched < self.ids.len()
+    }
 
-    let response = client.get(url).send().await?;
-    let ids: Vec<u64> = response.json().await?;
+    /// Replaces the id list and stories with a freshly fetched first page
+    fn set_first_page(&mut self, ids: Vec<u64>, page: Fetched, page_size: usize) {
+        self.fetched = ids.len().min(page_size);
+        self.ids = ids;
+        self.stories = page.items;
+        self.failed = page.failed;
+        self.selected = 0;
+        self.offset = 0;
+        self.loading = false;
+        self.loading_more = false;
+        self.error = None;
+    }
 
-    Ok(ids.into_iter().take(30).collect())
-}
+    /// Marks the list as being fetched, forgetting an earlier failure.
+    ///
+    /// Cancels the fetches still running for the list and returns the generation
+    /// of the new one.
+    fn start_loading(&mut self) -> u64 {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        // The cancelled page is requested again once the list has loaded
+        if self.loading_more {
+            self.fetched = self.page_start;
+            self.loading_more = false;
+        }
+        self.generation += 1;
+        self.loading = true;
+        self.progress = 0;
+        self.error = None;
+        self.generation
+    }
 
-/// Fetches a single story item by its ID from Hacker News API
-async fn fetch_item(client: &Client, id: u64) -> Result<Item> {
-    let url = format!("https://hacker-news.firebaseio.com/v0/item/{}.json", id);
+    /// Keeps a spawned fetch of the list so that reloading it cancels the fetch
+    fn track(&mut self, task: JoinHandle<()>) {
+        self.tasks.retain(|task| !task.is_finished());
+        self.tasks.push(task);
+    }
 
-    let response = client.get(&url).send().await?;
-    let item: Item = response.json().await?;
+    /// Indexes of the stories shown, in display order
+    fn rows(&self) -> Vec<usize> {
+        let words = filter::words(&self.filter);
+        let mut rows: Vec<usize> = (0..self.stories.len())
+            .filter(|&index| {
+                let story = &self.stories[index];
+                !self.hidden.contains(&story.id) && filter::matches(story, &words)
+            })
+            .collect();
+        self.sort.arrange(&self.stories, &mut rows);
+        rows
+    }
 
-    Ok(item)
-}
+    /// Replaces the filter, keeping the selection on a story that still matches
+    fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.settle_selection();
+    }
 
-/// Fetches the top 30 stories from Hacker News with progress updates
-async fn fetch_stories_with_progress<F>(progress_callback: F) -> Result<Vec<Item>>
-where
-    F: Fn(u16),
-{
-    let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+    /// Moves the selection to the next or previous match of the filter, wrapping
+    /// around at either end
+    fn select_match(&mut self, forward: bool) {
+        if self.filter.trim().is_empty() {
+            return;
+        }
+        let count = self.rows().len();
+        let Some(row) = self.selected_row().filter(|_| count > 0) else {
+            return;
+        };
+        let row = if forward {
+            (row + 1) % count
+        } else {
+            (row + count - 1) % count
+        };
+        self.select_row(row);
+    }
 
-    progress_callback(10);
+    /// Position of the selected story among the rows shown
+    fn selected_row(&self) -> Option<usize> {
+        self.rows().iter().position(|&index| index == self.selected)
+    }
 
-    let ids = fetch_top_story_ids(&client).await?;
-    progress_callback(20);
+    /// The selected story, unless it is hidden
+    fn selected_story(&self) -> Option<&Item> {
+        self.stories
+            .get(self.selected)
+            .filter(|story| !self.hidden.contains(&story.id))
+    }
 
-    let mut stories = Vec::new();
-    let total_ids = ids.len() as f32;
+    /// Selects the story shown at a row.
+    ///
+    /// Returns false if no story is shown there.
+    fn select_row(&mut self, row: usize) -> bool {
+        match self.rows().get(row) {
+            Some(&index) => {
+                self.selected = index;
+                true
+            }
+            None => false,
+        }
+    }
 
-    for (index, id) in ids.iter().enumerate() {
-        match fetch_item(&client, *id).await {
-            Ok(item) => stories.push(item),
-            Err(e) => eprintln!("Failed to fetch item {}: {}", id, e),
+    /// Moves the selection to the next row shown
+    fn select_next(&mut self) {
+        if let Some(row) = self.selected_row() {
+            self.select_row(row + 1);
         }
+    }
 
-        // Update progress (20% to 90% for fetching items)
-        let progress = 20 + ((index as f32 / total_ids) * 70.0) as u16;
-        progress_callback(progress);
+    /// Moves the selection to the previous row shown
+    fn select_previous(&mut self) {
+        if let Some(row) = self.selected_row() {
+            self.select_row(row.saturating_sub(1));
+        }
     }
 
-    progress_callback(100);
-    Ok(stories)
-}
+    /// Moves the selection to the first row shown
+    fn select_first(&mut self) {
+        self.select_row(0);
+    }
 
-/// Fetches the top 30 Show HN stories from Hacker News with progress updates
-async fn fetch_show_stories_with_progress<F>(progress_callback: F) -> Result<Vec<Item>>
-where
-    F: Fn(u16),
-{
-    let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+    /// Moves the selection to the last row shown
+    fn select_last(&mut self) {
+        let count = self.rows().len();
+        self.select_row(count.saturating_sub(1));
+    }
 
-    progress_callback(10);
+    /// Moves the selection off a story that is no longer shown, to the next one
+    /// below it or else the last one
+    fn settle_selection(&mut self) {
+        let rows = self.rows();
+        if !rows.contains(&self.selected) {
+            self.selected = rows
+                .iter()
+                .copied()
+                .find(|&index| index > self.selected)
+                .or(rows.last().copied())
+                .unwrap_or(0);
+        }
+    }
 
-    let ids = fetch_show_story_ids(&client).await?;
-    progress_callback(20);
+    /// Reserves the next page if the selection is near the end of the rows shown.
+    ///
+    /// Returns the index of the page's first id and the ids to fetch.
+    fn reserve_page(&mut self, page_size: usize) -> Option<(usize, Vec<u64>)> {
+        let shown = self.rows().len();
+        let near_end = self
+            .selected_row()
+            .is_none_or(|row| row + PREFETCH_MARGIN >= shown);
+        if !near_end || !self.has_more() || self.loading || self.loading_more {
+            return None;
+        }
 
-    let mut stories = Vec::new();
-    let total_ids = ids.len() as f32;
+        let start = self.fetched;
+        let end = (start + page_size).min(self.ids.len());
+        self.fetched = end;
+        self.page_start = start;
+        self.loading_more = true;
+        Some((start, self.ids[start..end].to_vec()))
+    }
 
-    for (index, id) in ids.iter().enumerate() {
-        match fetch_item(&client, *id).await {
-            Ok(item) => stories.push(item),
-            Err(e) => eprintln!("Failed to fetch item {}: {}", id, e),
+    /// Appends a fetched page if it is the one this list is waiting for
+    fn append_page(&mut self, start: usize, page: Fetched, page_size: usize) {
+        if self.loading_more && self.fetched == (start + page_size).min(self.ids.len()) {
+            self.stories.extend(page.items);
+            self.failed += page.failed;
+            self.loading_more = false;
         }
+    }
 
-        // Update progress (20% to 90% for fetching items)
-        let progress = 20 + ((index as f32 / total_ids) * 70.0) as u16;
-        progress_callback(progress);
+    /// Ids of the stories around the selection, which live updates keep current
+    fn visible_ids(&self) -> Vec<u64> {
+        let start = self.selected.saturating_sub(LIVE_WINDOW / 2);
+        self.stories
+            .iter()
+            .skip(start)
+            .take(LIVE_WINDOW)
+            .map(|story| story.id)
+            .collect()
     }
 
-    progress_callback(100);
-    Ok(stories)
-}
+    /// Records a live change to a story, merging it with recent ones
+    fn note_change(&mut self, id: u64, update: impl FnOnce(&mut Change)) {
+        self.changes.retain(|_, change| change.is_recent());
+        let change = self.changes.entry(id).or_insert(Change {
+            entered: false,
+            moved: 0,
+            score: 0,
+            comments: 0,
+            at: Instant::now(),
+        });
+        change.at = Instant::now();
+        update(change);
+    }
 
-// ===== UI FUNCTIONS =====
+    /// Applies a live id list, reordering the loaded stories by their new rank.
+    ///
+    /// Returns the ids that entered the loaded range and still need fetching.
+    fn apply_live_ids(&mut self, ids: Vec<u64>) -> Vec<u64> {
+        // Leave the list alone while a fetch is in flight; the next update catches up
+        if self.stories.is_empty() || self.loading || self.loading_more {
+            return Vec::new();
+        }
 
-/// Renders the user interface for the Hacker News application
-fn ui(f: &mut Frame, app: &mut App) {
-    match &app.state {
-        AppState::Loading => render_loading_screen(f, app),
-        AppState::Stories => render_stories_screen(f, app),
-        AppState::ShowHN => render_show_hn_screen(f, app),
-        AppState::Error(error) => render_error_screen(f, error),
+        let old_fetched = self.fetched;
+        let old_ranks: HashMap<u64, usize> = self
+            .ids
+            .iter()
+            .enumerate()
+            .map(|(rank, &id)| (id, rank))
+            .collect();
+        let selected_id = self.stories.get(self.selected).map(|story| story.id);
+        let mut loaded: HashMap<u64, Item> = self
+            .stories
+            .drain(..)
+            .map(|story| (story.id, story))
+            .collect();
+
+        let fetched = old_fetched.min(ids.len());
+        let mut missing = Vec::new();
+        for (rank, &id) in ids[..fetched].iter().enumerate() {
+            match loaded.remove(&id) {
+                Some(story) => self.stories.push(story),
+                // Ids already in the loaded range that failed to load are not retried
+                None if old_ranks.get(&id).is_none_or(|&old| old >= old_fetched) => {
+                    missing.push(id)
+                }
+                None => {}
+            }
+            match old_ranks.get(&id) {
+                Some(&old) if old != rank => {
+                    self.note_change(id, |change| change.moved += old as i64 - rank as i64)
+                }
+                None => self.note_change(id, |change| change.entered = true),
+                _ => {}
+            }
+        }
+
+        self.ids = ids;
+        self.fetched = fetched;
+        self.selected = selected_id
+            .and_then(|id| self.stories.iter().position(|story| story.id == id))
+            .unwrap_or(self.selected)
+            .min(self.stories.len().saturating_sub(1));
+        missing
     }
-}
 
-/// Renders the loading screen
-fn render_loading_screen(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(35),
-            Constraint::Length(5),
-            Constraint::Length(1),
-            Constraint::Length(5),
-            Constraint::Percentage(35),
-        ])
-        .split(f.area());
+    /// Patches loaded stories with refreshed copies, noting score and comment changes.
+    ///
+    /// Stories that entered the loaded range are inserted at their rank.
+    fn apply_live_items(&mut self, items: Vec<Item>) {
+        let ranks: HashMap<u64, usize> = self.ids[..self.fetched]
+            .iter()
+            .enumerate()
+            .map(|(rank, &id)| (id, rank))
+            .collect();
+        let selected_id = self.stories.get(self.selected).map(|story| story.id);
 
-    // Title with border
-    let title = Paragraph::new("📰 Hacker News TUI")
-        .style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Cyan))
-                .title("Welcome"),
-        );
-    f.render_widget(title, chunks[1]);
+        for item in items {
+            if let Some(story) = self.stories.iter_mut().find(|story| story.id == item.id) {
+                let score =
+                    i64::from(item.score.unwrap_or(0)) - i64::from(story.score.unwrap_or(0));
+                let comments = i64::from(item.descendants.unwrap_or(0))
+                    - i64::from(story.descendants.unwrap_or(0));
+                *story = item;
+                if score != 0 || comments != 0 {
+                    let id = story.id;
+                    self.note_change(id, |change| {
+                        change.score += score;
+                        change.comments += comments;
+                    });
+                }
+            } else if let Some(&rank) = ranks.get(&item.id) {
+                let index = self
+                    .stories
+                    .iter()
+                    .take_while(|story| ranks.get(&story.id).is_some_and(|&r| r < rank))
+                    .count();
+                self.stories.insert(index, item);
+            }
+        }
 
-    // Loading bar with better styling
-    let gauge = Gauge::default()
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Loading Stories...")
-                .border_style(Style::default().fg(Color::White))
-                .title_style(
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                ),
-        )
-        .gauge_style(Style::default().fg(Color::Cyan).bg(Color::DarkGray))
-        .percent(app.loading_progress)
-        .label(format!("{}%", app.loading_progress));
-    f.render_widget(gauge, chunks[3]);
+        if let Some(id) = selected_id {
+            if let Some(index) = self.stories.iter().position(|story| story.id == id) {
+                self.selected = index;
+            }
+        }
+    }
+
+    /// The recent live change to a story, if any
+    fn change(&self, id: u64) -> Option<&Change> {
+        self.changes.get(&id).filter(|change| change.is_recent())
+    }
 }
 
-/// Renders the error screen
-fn render_error_screen(f: &mut Frame, error: &str) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(30),
-            Constraint::Length(7),
-            Constraint::Length(1),
-            Constraint::Length(5),
-            Constraint::Percentage(30),
-        ])
-        .split(f.area());
+/// Settings shared by every background fetch
+#[derive(Debug, Clone)]
+struct FetchOptions {
+    /// Maximum number of item requests in flight at once
+    concurrency: usize,
+    /// Number of stories fetched per page
+    page_size: usize,
+    /// On-disk cache consulted before the network
+    cache: Cache,
+    /// Source of feeds, items, users and search results
+    client: Arc<dyn HnClient>,
+}
 
-    // Error message with better formatting
-    let error_msg = Paragraph::new(format!("❌ Connection Failed\n\n{}", error))
-        .style(Style::default().fg(Color::White))
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Red))
-                .title("Error")
-                .title_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
-        );
-    f.render_widget(error_msg, chunks[1]);
+/// State of the search screen
+#[derive(Debug, Default)]
+struct SearchView {
+    /// Text typed into the search prompt
+    input: String,
+    /// Whether keystrokes go to the prompt rather than the result list
+    editing: bool,
+    /// Query the current results belong to
+    query: Option<SearchQuery>,
+    /// Results loaded so far
+    results: Vec<Item>,
+    /// Number of result pages loaded or requested
+    pages_requested: usize,
+    /// Total number of result pages available
+    total_pages: usize,
+    /// Index of the currently selected result
+    selected: usize,
+    /// Whether a page of results is being fetched
+    loading: bool,
+    /// Message to show instead of results when the query or request failed
+    error: Option<String>,
+    /// Number of the latest search submitted; results of earlier ones are dropped
+    generation: u64,
+}
 
-    // Instructions with border
-    let instructions = Paragraph::new("Press 'R' to retry or 'Q' to quit")
-        .style(
+/// Field of the Saved view being typed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SavedPrompt {
+    /// The filter, applied as it is typed
+    Filter,
+    /// Tags of the selected bookmark
+    Tags,
+    /// Note of the selected bookmark
+    Note,
+}
+
+/// State of the Saved view
+#[derive(Debug, Default)]
+struct SavedView {
+    /// Words every listed bookmark must match
+    filter: String,
+    /// Index of the selected bookmark among the matching ones
+    selected: usize,
+    /// Field being typed into, if any
+    prompt: Option<SavedPrompt>,
+    /// Text typed into the tags or note prompt
+    input: String,
+    /// Message to show if the bookmarks could not be saved
+    error: Option<String>,
+}
+
+/// State of the user profile screen
+#[derive(Debug, Default)]
+struct UserView {
+    /// Username whose profile is shown
+    name: String,
+    /// Profile, once fetched
+    user: Option<User>,
+    /// The user's submissions, paged like a feed
+    submissions: FeedView,
+    /// Message to show if the profile could not be loaded
+    error: Option<String>,
+}
+
+/// State of the article reader
+#[derive(Debug, Default)]
+struct ReaderView {
+    /// Story whose web page is shown
+    story: Option<Item>,
+    /// Extracted article, once downloaded
+    article: Option<Article>,
+    /// Index of the first line shown
+    scroll: usize,
+    /// Height of the text pane at the last render, used for paging
+    page_height: usize,
+    /// Whether the page is being downloaded
+    loading: bool,
+    /// Message to show if the page could not be loaded
+    error: Option<String>,
+}
+
+impl ReaderView {
+    /// URL of the story being read
+    fn url(&self) -> Option<&str> {
+        self.story.as_ref()?.url.as_deref()
+    }
+
+    /// Scrolls down by `lines`; rendering clamps it to the end of the article
+    fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_add(lines);
+    }
+
+    /// Scrolls up by `lines`
+    fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+}
+
+/// Application state enum to handle different screens
+#[derive(Debug, Clone, PartialEq)]
+enum AppState {
+    Stories,
+    Comments,
+    Search,
+    User,
+    Reader,
+    Saved,
+}
+
+/// Window drawn over the current screen
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+enum Overlay {
+    #[default]
+    None,
+    /// Key bindings of the current screen, scrolled by this many lines
+    Help { scroll: u16 },
+    /// Fuzzy search over every action, the current screen's first
+    Palette(Palette),
+}
+
+/// Rows of a rendered list, for mapping mouse positions back to items
+#[derive(Debug, Clone)]
+struct ListTarget {
+    /// Inner area of the list, inside its borders
+    area: Rect,
+    /// Index of the first item drawn
+    offset: usize,
+    /// Height in lines of every item
+    heights: Vec<usize>,
+}
+
+impl ListTarget {
+    /// Index of the item drawn at a screen position
+    fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        if !self.area.contains((x, y).into()) {
+            return None;
+        }
+        let mut top = self.area.y as usize;
+        for (index, &height) in self.heights.iter().enumerate().skip(self.offset) {
+            top += height;
+            if (y as usize) < top {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
+
+/// Screen regions that respond to the mouse, recorded by the last render
+#[derive(Debug, Default)]
+struct MouseTargets {
+    /// Feed tabs of the stories screen
+    tabs: Vec<(Rect, Feed)>,
+    /// Clickable footer entries
+    buttons: Vec<(Rect, Action)>,
+    /// The list of the current screen
+    list: Option<ListTarget>,
+}
+
+/// Top comments previewed in the detail pane for the selected story
+#[derive(Debug, Default)]
+struct DetailView {
+    /// Story the preview belongs to
+    story_id: Option<u64>,
+    /// Its first top-level comments
+    comments: Vec<Item>,
+    /// Whether the comments are being fetched
+    loading: bool,
+}
+
+/// Connection state of the live subscription for the current feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LiveStatus {
+    /// No subscription, as in offline mode
+    Off,
+    /// Trying to open the event stream
+    Connecting,
+    /// Changes arrive as they happen
+    Streaming,
+    /// Streaming is unavailable and the feed is polled
+    Polling,
+}
+
+/// Main application state
+#[derive(Debug)]
+struct App {
+    /// Feed currently shown in the story list
+    feed: Feed,
+    /// Stories and selection for every feed
+    feeds: HashMap<Feed, FeedView>,
+    /// Current application state
+    state: AppState,
+    /// Whether to show detailed info (score, author, comments, time)
+    show_info: bool,
+    /// Settings passed to every background fetch
+    fetch: FetchOptions,
+    /// Comment reader for the currently opened story
+    comments: CommentView,
+    /// Screen to return to when leaving the comment reader
+    comments_origin: AppState,
+    /// Search prompt and results
+    search: SearchView,
+    /// Profile of the user being viewed
+    user: UserView,
+    /// Screen to return to when leaving the user profile
+    user_origin: AppState,
+    /// State of the live subscription for the current feed
+    live: LiveStatus,
+    /// Article reader for the currently opened story
+    reader: ReaderView,
+    /// Screen to return to when leaving the reader
+    reader_origin: AppState,
+    /// Saved stories
+    bookmarks: Bookmarks,
+    /// Filter and selection of the Saved view
+    saved: SavedView,
+    /// Screen to return to when leaving the Saved view
+    saved_origin: AppState,
+    /// Opened items and the last seen stories of every feed
+    history: History,
+    /// Rules hiding stories from the feeds
+    killfile: Killfile,
+    /// Whether stories the killfile hides are shown anyway
+    reveal_hidden: bool,
+    /// Whether keystrokes go to the filter of the story list being browsed
+    filtering: bool,
+    /// Short message shown over the footer, and when it was set
+    notice: Option<(String, Instant)>,
+    /// Whether the last render had room for the detail pane
+    wide_layout: bool,
+    /// Preview of the selected story for the detail pane
+    detail: DetailView,
+    /// Colors of the story list
+    theme: Theme,
+    /// Display toggles from the config file
+    ui: UiSettings,
+    /// Key bindings of every screen
+    keymap: Keymap,
+    /// Help or command palette shown over the current screen
+    overlay: Overlay,
+    /// Clickable regions of the last rendered frame
+    mouse: MouseTargets,
+    /// Time and row of the last click, to detect double clicks
+    last_click: Option<(Instant, usize)>,
+}
+
+// ===== APP IMPLEMENTATION =====
+
+impl App {
+    /// Creates a new App instance with the user's configured defaults
+    fn new(
+        fetch: FetchOptions,
+        config: Config,
+        keymap: Keymap,
+        bookmarks: Bookmarks,
+        history: History,
+        killfile: Killfile,
+    ) -> Self {
+        Self {
+            feed: config.feeds.default,
+            feeds: Feed::ALL
+                .iter()
+                .map(|&feed| (feed, FeedView::for_feed(feed)))
+                .collect(),
+            state: AppState::Stories,
+            show_info: config.ui.show_info,
+            fetch,
+            comments: CommentView::default(),
+            comments_origin: AppState::Stories,
+            search: SearchView::default(),
+            user: UserView::default(),
+            user_origin: AppState::Stories,
+            live: LiveStatus::Off,
+            reader: ReaderView::default(),
+            reader_origin: AppState::Stories,
+            bookmarks,
+            saved: SavedView::default(),
+            saved_origin: AppState::Stories,
+            history,
+            killfile,
+            reveal_hidden: false,
+            filtering: false,
+            notice: None,
+            wide_layout: false,
+            detail: DetailView::default(),
+            theme: config.theme,
+            ui: config.ui,
+            keymap,
+            overlay: Overlay::None,
+            mouse: MouseTargets::default(),
+            last_click: None,
+        }
+    }
+
+    /// Key binding layer of the current screen
+    fn mode(&self) -> Mode {
+        match self.state {
+            AppState::Stories => Mode::Stories,
+            AppState::Search => Mode::Search,
+            AppState::User => Mode::User,
+            AppState::Comments => Mode::Comments,
+            AppState::Reader => Mode::Reader,
+            AppState::Saved => Mode::Saved,
+        }
+    }
+
+    /// Toggles the info display
+    fn toggle_info(&mut self) {
+        self.show_info = !self.show_info;
+    }
+
+    /// State of the feed currently shown
+    fn current_feed(&self) -> &FeedView {
+        &self.feeds[&self.feed]
+    }
+
+    /// Mutable state of the feed currently shown
+    fn current_feed_mut(&mut self) -> &mut FeedView {
+        self.feeds
+            .entry(self.feed)
+            .or_insert_with(|| FeedView::for_feed(self.feed))
+    }
+
+    /// The story list of the current screen, if it shows one
+    fn story_list(&self) -> Option<&FeedView> {
+        match self.state {
+            AppState::Stories => Some(self.current_feed()),
+            AppState::User => Some(&self.user.submissions),
+            _ => None,
+        }
+    }
+
+    /// Mutable story list of the current screen, if it shows one
+    fn story_list_mut(&mut self) -> Option<&mut FeedView> {
+        match self.state {
+            AppState::Stories => Some(self.current_feed_mut()),
+            AppState::User => Some(&mut self.user.submissions),
+            _ => None,
+        }
+    }
+
+    /// Opens the filter prompt of the current story list
+    fn start_filter(&mut self) {
+        self.filtering = self.story_list().is_some();
+    }
+
+    /// Switches the story list to another feed.
+    ///
+    /// Returns true if the feed has nothing loaded yet and should be fetched.
+    fn switch_feed(&mut self, feed: Feed) -> bool {
+        self.feed = feed;
+        let state = self.current_feed_mut();
+        state.stories.is_empty() && !state.loading
+    }
+
+    /// Moves selection to the next story if available
+    fn next(&mut self) {
+        match self.state {
+            AppState::Stories => self.current_feed_mut().select_next(),
+            AppState::Comments => self.comments.next(),
+            AppState::Search if self.search.selected + 1 < self.search.results.len() => {
+                self.search.selected += 1;
+            }
+            AppState::User => self.user.submissions.select_next(),
+            AppState::Saved if self.saved.selected + 1 < self.saved_matches().len() => {
+                self.saved.selected += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves selection to the previous story if available
+    fn previous(&mut self) {
+        match self.state {
+            AppState::Stories => self.current_feed_mut().select_previous(),
+            AppState::Comments => self.comments.previous(),
+            AppState::Search => {
+                self.search.selected = self.search.selected.saturating_sub(1);
+            }
+            AppState::User => self.user.submissions.select_previous(),
+            AppState::Saved => {
+                self.saved.selected = self.saved.selected.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves selection to the first story or comment
+    fn select_first(&mut self) {
+        match self.state {
+            AppState::Stories => self.current_feed_mut().select_first(),
+            AppState::Comments => self.comments.selected = 0,
+            AppState::Search => self.search.selected = 0,
+            AppState::User => self.user.submissions.select_first(),
+            AppState::Saved => self.saved.selected = 0,
+            _ => {}
+        }
+    }
+
+    /// Moves selection to the last loaded story or visible comment
+    fn select_last(&mut self) {
+        match self.state {
+            AppState::Stories => self.current_feed_mut().select_last(),
+            AppState::Comments => {
+                if let Some(&last) = self.comments.visible().last() {
+                    self.comments.selected = last;
+                }
+            }
+            AppState::Search => {
+                self.search.selected = self.search.results.len().saturating_sub(1);
+            }
+            AppState::User => self.user.submissions.select_last(),
+            AppState::Saved => {
+                self.saved.selected = self.saved_matches().len().saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Selects the item at a row of the current list.
+    ///
+    /// Returns false if the row holds no item, like the "loading more" placeholder.
+    fn select_row(&mut self, row: usize) -> bool {
+        let (selected, len) = match self.state {
+            AppState::Stories => return self.current_feed_mut().select_row(row),
+            AppState::User => return self.user.submissions.select_row(row),
+            AppState::Search => (&mut self.search.selected, self.search.results.len()),
+            AppState::Saved => {
+                let len = self.bookmarks.matching(&self.saved.filter).len();
+                (&mut self.saved.selected, len)
+            }
+            AppState::Comments => match self.comments.visible().get(row) {
+                Some(&index) => {
+                    self.comments.selected = index;
+                    return true;
+                }
+                None => return false,
+            },
+            _ => return false,
+        };
+        if row < len {
+            *selected = row;
+        }
+        row < len
+    }
+
+    /// Returns a reference to the currently selected story
+    fn selected_story(&self) -> Option<&Item> {
+        match self.state {
+            AppState::Stories => self.current_feed().selected_story(),
+            AppState::Search => self.search.results.get(self.search.selected),
+            AppState::User => self.user.submissions.selected_story(),
+            AppState::Saved => {
+                let index = *self.saved_matches().get(self.saved.selected)?;
+                Some(&self.bookmarks.entries()[index].item)
+            }
+            _ => None,
+        }
+    }
+
+    /// The list of a feed, unless `generation` belongs to an earlier fetch of it
+    /// whose results are stale
+    fn fetched_feed(&mut self, feed: Feed, generation: u64) -> Option<&mut FeedView> {
+        self.feeds
+            .get_mut(&feed)
+            .filter(|state| state.generation == generation)
+    }
+
+    /// Sets the id list and first page of a feed
+    fn set_stories(&mut self, feed: Feed, generation: u64, ids: Vec<u64>, stories: Fetched) {
+        let page_size = self.fetch.page_size;
+        let Some(state) = self.fetched_feed(feed, generation) else {
+            return;
+        };
+        state.set_first_page(ids, stories, page_size);
+        let loaded = state.stories.iter().map(|story| story.id).collect();
+        self.history.record_feed(feed, loaded);
+        self.apply_killfile(feed);
+    }
+
+    /// Reserves the next page of the current feed if the selection is near the end.
+    ///
+    /// Returns the feed, the generation of its list, the index of the page's first
+    /// id and the ids to fetch.
+    fn request_more(&mut self) -> Option<(Feed, u64, usize, Vec<u64>)> {
+        if self.state != AppState::Stories {
+            return None;
+        }
+        let (feed, page_size) = (self.feed, self.fetch.page_size);
+        let state = self.current_feed_mut();
+        let (start, ids) = state.reserve_page(page_size)?;
+        Some((feed, state.generation, start, ids))
+    }
+
+    /// Appends a fetched page if it is the one the feed is waiting for
+    fn append_stories(&mut self, feed: Feed, generation: u64, start: usize, stories: Fetched) {
+        let page_size = self.fetch.page_size;
+        let Some(state) = self.fetched_feed(feed, generation) else {
+            return;
+        };
+        state.append_page(start, stories, page_size);
+        let loaded = state.stories.iter().map(|story| story.id).collect();
+        self.history.record_feed(feed, loaded);
+        self.apply_killfile(feed);
+    }
+
+    /// Recomputes which loaded stories of a feed the killfile hides
+    fn apply_killfile(&mut self, feed: Feed) {
+        let state = self
+            .feeds
+            .entry(feed)
+            .or_insert_with(|| FeedView::for_feed(feed));
+        state.hidden = if self.reveal_hidden {
+            HashSet::new()
+        } else {
+            state
+                .stories
+                .iter()
+                .filter(|story| self.killfile.check(story).is_some())
+                .map(|story| story.id)
+                .collect()
+        };
+        state.settle_selection();
+    }
+
+    /// Hides the selected story's domain or author in every feed from now on
+    fn hide_selected(&mut self, action: Action) {
+        let Some(story) = self.selected_story() else {
+            return;
+        };
+        let result = match action {
+            Action::HideDomain => match story.domain() {
+                Some(domain) => {
+                    let domain = domain.to_string();
+                    self.killfile.hide_domain(&domain).map(|_| domain)
+                }
+                None => return,
+            },
+            _ => match story.by.clone() {
+                Some(user) => self.killfile.hide_user(&user).map(|_| user),
+                None => return,
+            },
+        };
+        let notice = match result {
+            Ok(hidden) => format!("Hid stories from {} • H reveals them", hidden),
+            Err(err) => format!("{:#}", err),
+        };
+        self.set_notice(notice);
+        for feed in Feed::ALL {
+            self.apply_killfile(feed);
+        }
+    }
+
+    /// Shows or hides the stories the killfile hides
+    fn toggle_reveal_hidden(&mut self) {
+        self.reveal_hidden = !self.reveal_hidden;
+        for feed in Feed::ALL {
+            self.apply_killfile(feed);
+        }
+        let notice = if self.reveal_hidden {
+            "Showing stories hidden by the killfile"
+        } else {
+            "Hiding stories matched by the killfile"
+        };
+        self.set_notice(notice.to_string());
+    }
+
+    /// Shows a short message over the footer
+    fn set_notice(&mut self, message: String) {
+        self.notice = Some((message, Instant::now()));
+    }
+
+    /// Starts reloading the current feed. Its stories stay listed until the new
+    /// ones arrive, and remain if the reload fails.
+    fn start_refresh(&mut self) {
+        // Stories are new if they were not there before the refresh
+        self.history.mark_seen(self.feed);
+        self.state = AppState::Stories;
+    }
+
+    /// Records that loading a feed failed, leaving the other feeds untouched
+    fn set_feed_error(&mut self, feed: Feed, generation: u64, error: String) {
+        let Some(state) = self.fetched_feed(feed, generation) else {
+            return;
+        };
+        state.loading = false;
+        state.loading_more = false;
+        state.error = Some(error);
+    }
+
+    /// Updates the loading progress of a feed
+    fn update_loading_progress(&mut self, feed: Feed, generation: u64, progress: u16) {
+        if let Some(state) = self.fetched_feed(feed, generation) {
+            state.progress = progress.min(100);
+        }
+    }
+
+    /// Applies a live event for a feed.
+    ///
+    /// Returns the ids of stories that must be refetched to bring the feed up to date.
+    fn handle_live(&mut self, feed: Feed, event: LiveEvent) -> Vec<u64> {
+        let state = self
+            .feeds
+            .entry(feed)
+            .or_insert_with(|| FeedView::for_feed(feed));
+        match event {
+            LiveEvent::Streaming | LiveEvent::Polling => {
+                if feed == self.feed {
+                    self.live = if event == LiveEvent::Streaming {
+                        LiveStatus::Streaming
+                    } else {
+                        LiveStatus::Polling
+                    };
+                }
+                Vec::new()
+            }
+            LiveEvent::FeedIds(ids) => state.apply_live_ids(ids),
+            LiveEvent::ItemsChanged(ids) => {
+                let changed: HashSet<u64> = ids.into_iter().collect();
+                state
+                    .visible_ids()
+                    .into_iter()
+                    .filter(|id| changed.contains(id))
+                    .collect()
+            }
+            LiveEvent::Tick => state.visible_ids(),
+        }
+    }
+
+    /// Patches a feed with refetched stories
+    fn apply_live_items(&mut self, feed: Feed, items: Vec<Item>) {
+        self.feeds
+            .entry(feed)
+            .or_insert_with(|| FeedView::for_feed(feed))
+            .apply_live_items(items);
+        self.apply_killfile(feed);
+    }
+
+    /// Opens the comment reader for the given story
+    fn open_comments(&mut self, story: Item) {
+        if self.state != AppState::Comments {
+            self.comments_origin = self.state.clone();
+        }
+        self.history.record_visit(&story);
+        self.comments = CommentView {
+            story: Some(story),
+            loading: true,
+            ..CommentView::default()
+        };
+        self.state = AppState::Comments;
+    }
+
+    /// Installs a fetched discussion if it belongs to the open story
+    fn set_comments(
+        &mut self,
+        story: Item,
+        poll_options: Vec<Item>,
+        tree: Vec<CommentNode>,
+        failed: usize,
+    ) {
+        if self.comments.story.as_ref().map(|s| s.id) == Some(story.id) {
+            // The comments now read are the fetched ones, not the list's count
+            self.history.record_visit(&story);
+            self.comments.story = Some(story);
+            self.comments.poll_options = poll_options;
+            self.comments.error = None;
+            self.comments.failed = failed;
+            self.comments.set_tree(tree);
+        }
+    }
+
+    /// Starts reloading the open discussion, keeping its comments shown until the
+    /// new ones arrive
+    fn reload_comments(&mut self) {
+        self.comments.loading = true;
+        self.comments.error = None;
+    }
+
+    /// Shows a failed discussion request inline in the comment reader
+    fn set_comments_error(&mut self, story_id: u64, error: String) {
+        if self.comments.story.as_ref().map(|s| s.id) == Some(story_id) {
+            self.comments.loading = false;
+            self.comments.error = Some(error);
+        }
+    }
+
+    /// Leaves the comment reader and returns to the originating list
+    fn close_comments(&mut self) {
+        self.state = self.comments_origin.clone();
+    }
+
+    /// Author of the selected story or comment on the current screen
+    fn selected_author(&self) -> Option<String> {
+        let item = match self.state {
+            AppState::Comments => self
+                .comments
+                .entries
+                .get(self.comments.selected)
+                .map(|entry| &entry.item),
+            _ => self.selected_story(),
+        };
+        item.and_then(|item| item.by.clone())
+    }
+
+    /// Opens the profile screen for a user
+    fn open_user(&mut self, name: String) {
+        if self.state != AppState::User {
+            self.user_origin = self.state.clone();
+        }
+        self.user = UserView {
+            name,
+            submissions: FeedView {
+                loading: true,
+                ..FeedView::default()
+            },
+            ..UserView::default()
+        };
+        self.state = AppState::User;
+    }
+
+    /// Starts reloading the open profile, keeping it shown until the new one arrives
+    fn reload_user(&mut self) {
+        self.user.submissions.loading = true;
+        self.user.error = None;
+    }
+
+    /// Installs a fetched profile and its first page of submissions
+    fn set_user(&mut self, user: User, submissions: Fetched) {
+        if self.user.name == user.id {
+            self.user.submissions.set_first_page(
+                user.submitted.clone(),
+                submissions,
+                self.fetch.page_size,
+            );
+            self.user.user = Some(user);
+        }
+    }
+
+    /// Shows a failed profile request inline on the profile screen
+    fn set_user_error(&mut self, name: String, error: String) {
+        if self.user.name == name {
+            self.user.submissions.loading = false;
+            self.user.error = Some(error);
+        }
+    }
+
+    /// Reserves the next page of the user's submissions if the selection is near the end
+    fn request_more_submissions(&mut self) -> Option<(String, usize, Vec<u64>)> {
+        let (start, ids) = self.user.submissions.reserve_page(self.fetch.page_size)?;
+        Some((self.user.name.clone(), start, ids))
+    }
+
+    /// Appends a page of submissions if it belongs to the profile being shown
+    fn append_submissions(&mut self, name: String, start: usize, items: Fetched) {
+        if self.user.name == name {
+            self.user
+                .submissions
+                .append_page(start, items, self.fetch.page_size);
+        }
+    }
+
+    /// Leaves the profile screen and returns to where it was opened from
+    fn close_user(&mut self) {
+        self.state = self.user_origin.clone();
+    }
+
+    /// Markers for a story's list row that do not depend on the list it is in
+    fn marks(&self, story: &Item) -> Marks<'_> {
+        Marks {
+            saved: self.bookmarks.contains(story.id),
+            visited: self.history.visit(story.id).is_some(),
+            new_comments: self.history.new_comments(story),
+            ..Marks::default()
+        }
+    }
+
+    /// Indexes of the bookmarks listed in the Saved view
+    fn saved_matches(&self) -> Vec<usize> {
+        self.bookmarks.matching(&self.saved.filter)
+    }
+
+    /// Opens the Saved view
+    fn open_saved(&mut self) {
+        if self.state != AppState::Saved {
+            self.saved_origin = self.state.clone();
+        }
+        self.saved.prompt = None;
+        self.state = AppState::Saved;
+        self.clamp_saved_selection();
+    }
+
+    /// Leaves the Saved view and returns to where it was opened from
+    fn close_saved(&mut self) {
+        self.saved.prompt = None;
+        self.state = self.saved_origin.clone();
+    }
+
+    /// Keeps the Saved view's selection on a listed bookmark
+    fn clamp_saved_selection(&mut self) {
+        let count = self.saved_matches().len();
+        self.saved.selected = self.saved.selected.min(count.saturating_sub(1));
+    }
+
+    /// Saves the story on the current screen, or removes it if it is already saved
+    fn toggle_bookmark(&mut self) {
+        let story = match self.state {
+            AppState::Comments => self.comments.story.clone(),
+            AppState::Reader => self.reader.story.clone(),
+            _ => self.selected_story().cloned(),
+        };
+        if let Some(story) = story {
+            let result = self.bookmarks.toggle(&story);
+            self.saved.error = result.err().map(|err| format!("{:#}", err));
+            self.clamp_saved_selection();
+        }
+    }
+
+    /// Opens a prompt of the Saved view, filled with the field's current value
+    fn start_saved_prompt(&mut self, prompt: SavedPrompt) {
+        let entry = self
+            .saved_matches()
+            .get(self.saved.selected)
+            .map(|&index| &self.bookmarks.entries()[index]);
+        self.saved.input = match (prompt, entry) {
+            (SavedPrompt::Filter, _) => String::new(),
+            (SavedPrompt::Tags, Some(entry)) => entry.tags.join(" "),
+            (SavedPrompt::Note, Some(entry)) => entry.note.clone().unwrap_or_default(),
+            // Tags and notes belong to a bookmark
+            (_, None) => return,
+        };
+        self.saved.prompt = Some(prompt);
+    }
+
+    /// Applies the tags or note typed into the Saved view's prompt
+    fn submit_saved_prompt(&mut self) {
+        let prompt = self.saved.prompt.take();
+        let Some(id) = self.selected_story().map(|story| story.id) else {
+            return;
+        };
+        let input = std::mem::take(&mut self.saved.input);
+        let result = match prompt {
+            Some(SavedPrompt::Tags) => {
+                let tags = input
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .map(|tag| tag.trim_start_matches('#'))
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                self.bookmarks.set_tags(id, tags)
+            }
+            Some(SavedPrompt::Note) => {
+                let note = Some(input.trim().to_string()).filter(|note| !note.is_empty());
+                self.bookmarks.set_note(id, note)
+            }
+            _ => Ok(()),
+        };
+        self.saved.error = result.err().map(|err| format!("{:#}", err));
+        self.clamp_saved_selection();
+    }
+
+    /// Points the detail pane at the selected story if it is showing another one.
+    ///
+    /// Returns the story id and the ids of the top comments to fetch, if any.
+    fn request_detail(&mut self) -> Option<(u64, Vec<u64>)> {
+        if !self.wide_layout || self.state != AppState::Stories {
+            return None;
+        }
+        let story = self.selected_story()?;
+        if self.detail.story_id == Some(story.id) {
+            return None;
+        }
+        let (id, kids) = (story.id, story.kids.clone());
+        self.detail = DetailView {
+            story_id: Some(id),
+            comments: Vec::new(),
+            loading: !kids.is_empty(),
+        };
+        if kids.is_empty() {
+            return None;
+        }
+        let ids = kids.into_iter().take(DETAIL_COMMENTS).collect();
+        Some((id, ids))
+    }
+
+    /// Installs fetched top comments if they belong to the story in the detail pane
+    fn set_detail(&mut self, story_id: u64, comments: Vec<Item>) {
+        if self.detail.story_id == Some(story_id) {
+            self.detail.comments = comments
+                .into_iter()
+                .filter(|comment| !comment.deleted && !comment.dead)
+                .collect();
+            self.detail.loading = false;
+        }
+    }
+
+    /// Opens the article reader for the given story
+    fn open_reader(&mut self, story: Item) {
+        if self.state != AppState::Reader {
+            self.reader_origin = self.state.clone();
+        }
+        self.history.record_visit(&story);
+        self.reader = ReaderView {
+            story: Some(story),
+            loading: true,
+            ..ReaderView::default()
+        };
+        self.state = AppState::Reader;
+    }
+
+    /// Opens the reader on a story's own text, as for Ask HN posts
+    fn open_post(&mut self, story: Item) {
+        let text = story.rich_text().unwrap_or_default();
+        self.open_reader(story);
+        self.reader.article = Some(Article { title: None, text });
+        self.reader.loading = false;
+    }
+
+    /// Installs an extracted article if it belongs to the open story
+    fn set_article(&mut self, url: String, article: Article) {
+        if self.reader.url() == Some(url.as_str()) {
+            self.reader.article = Some(article);
+            self.reader.loading = false;
+        }
+    }
+
+    /// Records why the open story's page could not be loaded
+    fn set_article_error(&mut self, url: String, error: String) {
+        if self.reader.url() == Some(url.as_str()) {
+            self.reader.error = Some(error);
+            self.reader.loading = false;
+        }
+    }
+
+    /// Leaves the reader
+    fn close_reader(&mut self) {
+        self.state = self.reader_origin.clone();
+    }
+
+    /// Shows the search screen with the prompt focused
+    fn open_search(&mut self) {
+        self.search.editing = true;
+        self.state = AppState::Search;
+    }
+
+    /// Leaves the search screen, keeping the last query and results
+    fn close_search(&mut self) {
+        self.search.editing = false;
+        self.state = AppState::Stories;
+    }
+
+    /// Parses the prompt and resets the results for a new search.
+    ///
+    /// Returns the query to run with the search's generation, or `None` if it could
+    /// not be parsed. Either way, results of earlier searches are dropped from now on.
+    fn submit_search(&mut self) -> Option<(SearchQuery, u64)> {
+        let search = &mut self.search;
+        search.generation += 1;
+        search.editing = false;
+        search.results.clear();
+        search.selected = 0;
+        search.total_pages = 0;
+
+        if self.fetch.cache.offline {
+            search.error = Some("Search is not available in offline mode".to_string());
+            return None;
+        }
+        match SearchQuery::parse(&search.input) {
+            Ok(query) => {
+                search.query = Some(query.clone());
+                search.pages_requested = 1;
+                search.loading = true;
+                search.error = None;
+                Some((query, search.generation))
+            }
+            Err(e) => {
+                search.query = None;
+                search.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Reserves the next page of search results if the selection is near the end.
+    ///
+    /// Returns the query, the generation of its search and the page to fetch.
+    fn request_more_results(&mut self) -> Option<(SearchQuery, u64, usize)> {
+        let search = &mut self.search;
+        let near_end = search.selected + PREFETCH_MARGIN >= search.results.len();
+        if !near_end || search.loading || search.pages_requested >= search.total_pages {
+            return None;
+        }
+
+        let page = search.pages_requested;
+        search.pages_requested += 1;
+        search.loading = true;
+        Some((search.query.clone()?, search.generation, page))
+    }
+
+    /// Appends a page of search results if it is the one the current search awaits
+    fn add_search_results(&mut self, generation: u64, page: usize, results: SearchPage) {
+        let search = &mut self.search;
+        if search.generation == generation && search.loading && search.pages_requested == page + 1 {
+            search.results.extend(results.items);
+            search.total_pages = results.pages;
+            search.loading = false;
+        }
+    }
+
+    /// Shows a failed search request inline on the search screen
+    fn set_search_error(&mut self, generation: u64, error: String) {
+        let search = &mut self.search;
+        if search.generation == generation {
+            search.loading = false;
+            search.error = Some(error);
+        }
+    }
+}
+
+impl CommentView {
+    /// Flattens a fetched comment tree into display order
+    fn set_tree(&mut self, tree: Vec<CommentNode>) {
+        fn flatten(
+            nodes: Vec<CommentNode>,
+            depth: usize,
+            parent: Option<usize>,
+            out: &mut Vec<CommentEntry>,
+        ) {
+            for node in nodes {
+                let index = out.len();
+                out.push(CommentEntry {
+                    text: node.item.rich_text().unwrap_or_default(),
+                    item: node.item,
+                    depth,
+                    parent,
+                    descendants: 0,
+                });
+                flatten(node.replies, depth + 1, Some(index), out);
+                out[index].descendants = out.len() - index - 1;
+            }
+        }
+
+        self.entries.clear();
+        flatten(tree, 0, None, &mut self.entries);
+        self.collapsed.clear();
+        self.selected = 0;
+        self.loading = false;
+    }
+
+    /// Returns the indices of entries not hidden inside a collapsed thread
+    fn visible(&self) -> Vec<usize> {
+        let mut visible = Vec::new();
+        let mut index = 0;
+        while index < self.entries.len() {
+            visible.push(index);
+            if self.collapsed.contains(&index) {
+                index += self.entries[index].descendants + 1;
+            } else {
+                index += 1;
+            }
+        }
+        visible
+    }
+
+    /// Moves to the next visible comment
+    fn next(&mut self) {
+        let visible = self.visible();
+        if let Some(pos) = visible.iter().position(|&i| i == self.selected) {
+            if let Some(&next) = visible.get(pos + 1) {
+                self.selected = next;
+            }
+        }
+    }
+
+    /// Moves to the previous visible comment
+    fn previous(&mut self) {
+        let visible = self.visible();
+        if let Some(pos) = visible.iter().position(|&i| i == self.selected) {
+            if pos > 0 {
+                self.selected = visible[pos - 1];
+            }
+        }
+    }
+
+    /// Moves to the parent of the selected comment
+    fn parent(&mut self) {
+        if let Some(parent) = self.entries.get(self.selected).and_then(|e| e.parent) {
+            self.selected = parent;
+        }
+    }
+
+    /// Moves to the first reply of the selected comment, expanding it if needed
+    fn first_child(&mut self) {
+        if let Some(entry) = self.entries.get(self.selected) {
+            if entry.descendants > 0 {
+                self.collapsed.remove(&self.selected);
+                self.selected += 1;
+            }
+        }
+    }
+
+    /// Moves to the next comment sharing the selected comment's parent
+    fn next_sibling(&mut self) {
+        if let Some(entry) = self.entries.get(self.selected) {
+            let candidate = self.selected + entry.descendants + 1;
+            if let Some(next) = self.entries.get(candidate) {
+                if next.parent == entry.parent {
+                    self.selected = candidate;
+                }
+            }
+        }
+    }
+
+    /// Moves to the previous comment sharing the selected comment's parent
+    fn previous_sibling(&mut self) {
+        if let Some(entry) = self.entries.get(self.selected) {
+            let parent = entry.parent;
+            let start = parent.map_or(0, |p| p + 1);
+            if let Some(index) = (start..self.selected)
+                .rev()
+                .find(|&i| self.entries[i].parent == parent)
+            {
+                self.selected = index;
+            }
+        }
+    }
+
+    /// Collapses or expands the replies of the selected comment
+    fn toggle_collapse(&mut self) {
+        let has_replies = self
+            .entries
+            .get(self.selected)
+            .is_some_and(|e| e.descendants > 0);
+        if has_replies && !self.collapsed.remove(&self.selected) {
+            self.collapsed.insert(self.selected);
+        }
+    }
+}
+
+// ===== API FUNCTIONS =====
+
+/// Fetches the story IDs of a feed, using the cache when possible.
+///
+/// `force` skips a fresh cached list, as when the user asks for a refresh. A stale
+/// cached list is still returned if the network request fails.
+async fn fetch_feed_ids(
+    client: &dyn HnClient,
+    cache: &Cache,
+    feed: Feed,
+    force: bool,
+) -> Result<Vec<u64>> {
+    let cached = cache.feed_ids(feed).await;
+    if let Some((ids, freshness)) = &cached {
+        if cache.offline || (!force && *freshness == Freshness::Fresh) {
+            return Ok(ids.clone());
+        }
+    }
+    if cache.offline {
+        bail!("No cached {} stories available offline", feed.label());
+    }
+
+    match (client.feed_ids(feed).await, cached) {
+        (Ok(ids), _) => {
+            cache.store_feed_ids(feed, &ids).await;
+            Ok(ids)
+        }
+        (Err(_), Some((ids, _))) => Ok(ids),
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Fetches a single item by its ID, using the cache when possible.
+///
+/// `force` skips a fresh cached copy, as when the user asks for a refresh. A stale
+/// cached copy is still returned if the network request fails.
+async fn fetch_item(client: &dyn HnClient, cache: &Cache, id: u64, force: bool) -> Result<Item> {
+    let cached = cache.item(id).await;
+    if let Some((item, freshness)) = &cached {
+        if cache.offline || (!force && *freshness == Freshness::Fresh) {
+            return Ok(item.clone());
+        }
+    }
+    if cache.offline {
+        bail!("item {} is not cached", id);
+    }
+
+    // A refresh must not be answered by a request that started before it
+    let result = if force {
+        client.fresh_item(id).await
+    } else {
+        client.item(id).await
+    };
+    match (result, cached) {
+        (Ok(item), _) => {
+            cache.store_item(&item).await;
+            Ok(item)
+        }
+        (Err(_), Some((item, _))) => Ok(item),
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Fetches a user profile, using the cache when possible.
+///
+/// `force` skips a fresh cached copy, as when the user asks for a refresh. A stale
+/// cached copy is still returned if the network request fails.
+async fn fetch_user(client: &dyn HnClient, cache: &Cache, name: &str, force: bool) -> Result<User> {
+    let cached = cache.user(name).await;
+    if let Some((user, freshness)) = &cached {
+        if cache.offline || (!force && *freshness == Freshness::Fresh) {
+            return Ok(user.clone());
+        }
+    }
+    if cache.offline {
+        bail!("Profile of {} is not cached", name);
+    }
+
+    match (client.user(name).await, cached) {
+        (Ok(user), _) => {
+            cache.store_user(&user).await;
+            Ok(user)
+        }
+        (Err(_), Some((user, _))) => Ok(user),
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Fetches items concurrently, keeping the order of `ids`.
+///
+/// At most `options.concurrency` requests are in flight at once. `force` skips fresh
+/// cached copies. `on_done` is called with the number of finished requests each time
+/// one completes, in completion order. Items that fail to load are left out and
+/// counted.
+async fn fetch_items<F>(options: &FetchOptions, ids: &[u64], force: bool, on_done: F) -> Fetched
+where
+    F: Fn(usize) + Sync,
+{
+    let done = AtomicUsize::new(0);
+    let results: Vec<Result<Item>> = stream::iter(ids.iter().copied())
+        .map(|id| {
+            let (done, on_done) = (&done, &on_done);
+            async move {
+                let result = fetch_item(options.client.as_ref(), &options.cache, id, force).await;
+                on_done(done.fetch_add(1, Ordering::Relaxed) + 1);
+                result
+            }
+        })
+        .buffered(options.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut fetched = Fetched::default();
+    for result in results {
+        match result {
+            Ok(item) => fetched.items.push(item),
+            Err(_) => fetched.failed += 1,
+        }
+    }
+    fetched
+}
+
+/// Fetches a feed's id list and its first page of stories with progress updates.
+///
+/// `force` skips the fresh cached id list and stories.
+async fn fetch_stories_with_progress<F>(
+    feed: Feed,
+    options: &FetchOptions,
+    force: bool,
+    progress_callback: F,
+) -> Result<(Vec<u64>, Fetched)>
+where
+    F: Fn(u16) + Sync,
+{
+    progress_callback(10);
+
+    let ids = fetch_feed_ids(options.client.as_ref(), &options.cache, feed, force).await?;
+    progress_callback(20);
+
+    let page = &ids[..ids.len().min(options.page_size)];
+    let total_ids = page.len() as f32;
+
+    let stories = fetch_items(options, page, force, |done| {
+        // Update progress (20% to 90% for fetching items)
+        let progress = 20 + ((done as f32 / total_ids) * 70.0) as u16;
+        progress_callback(progress);
+    })
+    .await;
+
+    progress_callback(100);
+    Ok((ids, stories))
+}
+
+/// Fetches a further page of stories by id
+async fn fetch_story_page(ids: Vec<u64>, options: &FetchOptions) -> Fetched {
+    fetch_items(options, &ids, false, |_| {}).await
+}
+
+/// Refetches items for a live update, bypassing the cache but refreshing it.
+///
+/// Items that fail to load are skipped.
+async fn fetch_live_items(ids: Vec<u64>, options: &FetchOptions) -> Vec<Item> {
+    let client = options.client.as_ref();
+    let results: Vec<Result<Item>> = stream::iter(ids)
+        .map(|id| client.item(id))
+        .buffered(options.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut items = Vec::new();
+    for item in results.into_iter().flatten() {
+        options.cache.store_item(&item).await;
+        items.push(item);
+    }
+    items
+}
+
+/// Downloads a story's web page and extracts its article
+async fn fetch_article(url: &str, options: &FetchOptions) -> Result<Article> {
+    if options.cache.offline {
+        bail!("Articles are not available offline");
+    }
+    let html = options.client.article(url).await?;
+    Ok(reader::extract(&html, url))
+}
+
+/// Fetches a user profile and the first page of their submissions.
+///
+/// `force` skips fresh cached copies of the profile and the submissions.
+async fn fetch_user_with_submissions(
+    name: &str,
+    options: &FetchOptions,
+    force: bool,
+) -> Result<(User, Fetched)> {
+    let user = fetch_user(options.client.as_ref(), &options.cache, name, force).await?;
+    let page = &user.submitted[..user.submitted.len().min(options.page_size)];
+    let submissions = fetch_items(options, page, force, |_| {}).await;
+    Ok((user, submissions))
+}
+
+/// Fetches the given comments and all of their replies.
+///
+/// The tree is fetched one level at a time, so no more than `options.concurrency`
+/// requests are in flight however large the thread is. `force` skips fresh cached
+/// comments. Also returns the number of comments that failed to load.
+async fn fetch_comment_tree(
+    options: &FetchOptions,
+    ids: &[u64],
+    force: bool,
+) -> (Vec<CommentNode>, usize) {
+    let mut levels: Vec<Vec<Item>> = Vec::new();
+    let mut failed = 0;
+    let mut next = ids.to_vec();
+    while !next.is_empty() {
+        let fetched = fetch_items(options, &next, force, |_| {}).await;
+        failed += fetched.failed;
+        let mut level = fetched.items;
+        // Skip removed comments unless replies hang off them
+        level.retain(|item| !((item.deleted || item.dead) && item.kids.is_empty()));
+        next = level.iter().flat_map(|item| item.kids.clone()).collect();
+        levels.push(level);
+    }
+
+    // Assemble the tree from the deepest level up
+    let mut nodes: HashMap<u64, CommentNode> = HashMap::new();
+    for level in levels.into_iter().rev() {
+        for item in level {
+            let replies = item
+                .kids
+                .iter()
+                .filter_map(|kid| nodes.remove(kid))
+                .collect();
+            nodes.insert(item.id, CommentNode { item, replies });
+        }
+    }
+    let tree = ids.iter().filter_map(|id| nodes.remove(id)).collect();
+    (tree, failed)
+}
+
+/// Fetches the full discussion for a story, plus its options if it is a poll.
+///
+/// The story is fetched again rather than taken from the list so its reply ids are
+/// current. `force` skips the cache, so a reload shows replies posted since. Also
+/// returns the number of poll options and comments that failed to load.
+async fn fetch_comments(
+    story_id: u64,
+    options: &FetchOptions,
+    force: bool,
+) -> Result<(Item, Vec<Item>, Vec<CommentNode>, usize)> {
+    let story = fetch_item(options.client.as_ref(), &options.cache, story_id, force).await?;
+    let poll_options = fetch_items(options, &story.parts, force, |_| {}).await;
+    let (tree, failed) = fetch_comment_tree(options, &story.kids, force).await;
+    Ok((
+        story,
+        poll_options.items,
+        tree,
+        poll_options.failed + failed,
+    ))
+}
+
+// ===== TEXT HELPERS =====
+
+/// Formats a Unix timestamp as a short relative age ("5m", "3h", "2d")
+fn time_ago(time: u64) -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let diff = now.saturating_sub(time);
+    if diff < 3600 {
+        format!("{}m", diff / 60)
+    } else if diff < 86400 {
+        format!("{}h", diff / 3600)
+    } else {
+        format!("{}d", diff / 86400)
+    }
+}
+
+/// Formats a Unix timestamp as a UTC calendar date ("2024-03-01")
+fn format_date(time: u64) -> String {
+    // Civil date from days since the Unix epoch (proleptic Gregorian calendar)
+    let days = (time / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// ===== UI FUNCTIONS =====
+
+/// Renders the user interface for the Hacker News application
+fn ui(f: &mut Frame, app: &mut App) {
+    app.mouse = MouseTargets::default();
+    match &app.state {
+        AppState::Stories => render_stories_screen(f, app),
+        AppState::Comments => render_comments_screen(f, app),
+        AppState::Search => render_search_screen(f, app),
+        AppState::User => render_user_screen(f, app),
+        AppState::Reader => render_reader_screen(f, app),
+        AppState::Saved => render_saved_screen(f, app),
+    }
+    if let Some((message, at)) = &app.notice {
+        if at.elapsed() < NOTICE_DURATION {
+            render_notice(f, message);
+            app.mouse.buttons.clear();
+        }
+    }
+    render_overlay(f, app);
+}
+
+/// Renders a short message in place of the footer at the bottom of the screen
+fn render_notice(f: &mut Frame, message: &str) {
+    let area = f.area();
+    let height = area.height.min(3);
+    let area = Rect::new(area.x, area.bottom() - height, area.width, height);
+    let notice = Paragraph::new(message)
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray)),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(notice, area);
+}
+
+/// Renders the controls footer and returns the screen regions of its clickable
+/// entries. Each entry is a label and the action clicking it performs, if any.
+fn render_footer(
+    f: &mut Frame,
+    area: Rect,
+    entries: &[(&'static str, Option<Action>)],
+) -> Vec<(Rect, Action)> {
+    let separator = " • ";
+    let mut spans = Vec::new();
+    for (index, (label, _)) in entries.iter().enumerate() {
+        if index > 0 {
+            spans.push(Span::raw(separator));
+        }
+        spans.push(Span::raw(*label));
+    }
+    let line = Line::from(spans);
+
+    // Same centering as Paragraph within the block's inner area
+    let inner = area.inner(Margin::new(1, 1));
+    let mut x = inner.x + (inner.width / 2).saturating_sub(line.width() as u16 / 2);
+    let mut buttons = Vec::new();
+    for (label, action) in entries {
+        let width = Line::from(*label).width() as u16;
+        if let Some(action) = action {
+            buttons.push((Rect::new(x, inner.y, width, 1).intersection(inner), *action));
+        }
+        x += width + Line::from(separator).width() as u16;
+    }
+
+    let footer = Paragraph::new(line)
+        .style(
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray))
+                .title("Controls")
+                .title_style(
+                    Style::default()
+                        .fg(Color::Gray)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        );
+    f.render_widget(footer, area);
+    buttons
+}
+
+/// A rectangle of at most `width` x `height` centered in `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Draws the help overlay or command palette over the current screen
+fn render_overlay(f: &mut Frame, app: &mut App) {
+    let mode = app.mode();
+    match &mut app.overlay {
+        Overlay::None => {}
+        Overlay::Help { scroll } => render_help(f, mode, &app.keymap.bindings(mode), scroll),
+        Overlay::Palette(palette) => {
+            render_palette(f, palette, &palette::entries(&app.keymap, mode))
+        }
+    }
+}
+
+/// Renders the key bindings of the current screen, as generated from the keymap
+fn render_help(f: &mut Frame, mode: Mode, entries: &[(Action, Vec<String>)], scroll: &mut u16) {
+    let keys: Vec<String> = entries.iter().map(|(_, keys)| keys.join(", ")).collect();
+    let key_width = keys
+        .iter()
+        .map(|keys| keys.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(8, 24);
+    let lines: Vec<Line> = entries
+        .iter()
+        .zip(keys)
+        .map(|((action, _), keys)| {
+            let keys = if keys.is_empty() {
+                Span::styled(
+                    format!("{:width$}", "(unbound)", width = key_width),
+                    Style::default().fg(Color::DarkGray),
+                )
+            } else {
+                Span::styled(
+                    format!("{:width$}", keys, width = key_width),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            };
+            Line::from(vec![
+                keys,
+                Span::raw("  "),
+                Span::styled(action.description(), Style::default().fg(Color::White)),
+            ])
+        })
+        .collect();
+
+    let area = centered(f.area(), 72, lines.len() as u16 + 2);
+    // Keep the last line at the bottom edge when scrolled too far
+    let max_scroll = (lines.len() as u16).saturating_sub(area.height.saturating_sub(2));
+    *scroll = (*scroll).min(max_scroll);
+
+    let help = Paragraph::new(lines).scroll((*scroll, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .padding(ratatui::widgets::Padding::horizontal(1))
+            .title(format!("❔ Keys: {}", mode.name()))
+            .title_bottom(Line::from(" j/k scroll • any other key closes ").right_aligned())
+            .title_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
+
+/// Renders the command palette with the actions matching what was typed
+fn render_palette(f: &mut Frame, palette: &mut Palette, entries: &[palette::Entry]) {
+    let matches = palette.matches(entries);
+    palette.selected = palette.selected.min(matches.len().saturating_sub(1));
+
+    let area = centered(f.area(), 64, (matches.len() as u16).clamp(1, 12) + 4);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title("⌘ Commands")
+        .title_bottom(Line::from(" ↑↓ select • Enter run • Esc close ").right_aligned())
+        .title_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Input
+            Constraint::Length(1), // Separator
+            Constraint::Min(0),    // Matches
+        ])
+        .split(inner);
+
+    let input = Paragraph::new(Line::from(vec![
+        Span::styled(
+            ": ",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(palette.input.clone(), Style::default().fg(Color::White)),
+        Span::styled("█", Style::default().fg(Color::Cyan)),
+    ]));
+    f.render_widget(input, chunks[0]);
+    f.render_widget(
+        Paragraph::new("─".repeat(chunks[1].width as usize))
+            .style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+
+    if matches.is_empty() {
+        let empty = Paragraph::new("No matching commands")
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(Alignment::Center);
+        f.render_widget(empty, chunks[2]);
+        return;
+    }
+    let items: Vec<ListItem> = matches
+        .iter()
+        .map(|&index| {
+            let entry = &entries[index];
+            let spans = match entry.elsewhere {
+                // Listed so it can be found, but it runs on another screen
+                Some(mode) => vec![
+                    Span::styled(
+                        entry.action.description(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("  on the {} screen", mode.name()),
+                        Style::default()
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                ],
+                None if entry.keys.is_empty() => vec![Span::raw(entry.action.description())],
+                None => vec![
+                    Span::raw(entry.action.description()),
+                    Span::styled(
+                        format!("  {}", entry.keys.join(", ")),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ],
+            };
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(
+            Style::default()
+                .bg(Color::Cyan)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("➤ ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(palette.selected));
+    f.render_stateful_widget(list, chunks[2], &mut list_state);
+}
+
+/// Renders the main stories screen for the current feed
+fn render_stories_screen(f: &mut Frame, app: &mut App) {
+    // Split the screen into header, stories, and footer sections
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Header with feed tabs
+            Constraint::Min(0),    // Stories list
+            Constraint::Length(3), // Footer
+        ])
+        .split(f.area());
+
+    let feed = app.feed;
+    let accent = feed.accent();
+
+    // Render the header as a tab bar of all feeds
+    let tab_titles: Vec<Line> = Feed::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, f)| {
+            let mut title = vec![Span::raw(format!(
+                "{} {} {}",
+                index + 1,
+                f.icon(),
+                f.label()
+            ))];
+            // Each feed shows whether it is loading or failed to load
+            let view = &app.feeds[f];
+            if view.loading {
+                title.push(Span::styled(
+                    format!(" {}", spinner()),
+                    Style::default().fg(Color::Yellow),
+                ));
+            } else if view.error.is_some() {
+                title.push(Span::styled(" ✗", Style::default().fg(Color::Red)));
+            }
+            Line::from(title)
+        })
+        .collect();
+    let tabs = Tabs::new(tab_titles.clone())
+        .select(feed.index())
+        .style(Style::default().fg(Color::Gray))
+        .highlight_style(
+            Style::default()
+                .fg(Color::White)
+                .bg(accent)
+                .add_modifier(Modifier::BOLD),
+        )
+        .divider(Span::styled("│", Style::default().fg(Color::DarkGray)))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(accent))
+                .title(match app.live {
+                    _ if app.fetch.cache.offline => "📰 Hacker News (offline)",
+                    LiveStatus::Streaming => "📰 Hacker News ● live",
+                    LiveStatus::Polling => "📰 Hacker News ↻ polling",
+                    LiveStatus::Connecting | LiveStatus::Off => "📰 Hacker News",
+                })
+                .title_style(Style::default().fg(accent).add_modifier(Modifier::BOLD)),
+        );
+    f.render_widget(tabs, chunks[0]);
+
+    // Each tab is padded by a space on both sides and followed by a divider
+    let mut x = chunks[0].x + 1;
+    for (feed, title) in Feed::ALL.iter().zip(&tab_titles) {
+        let width = title.width() as u16 + 2;
+        let area = Rect::new(x, chunks[0].y + 1, width, 1).intersection(chunks[0]);
+        app.mouse.tabs.push((area, *feed));
+        x += width + 1;
+    }
+
+    // Wide terminals show the selected story's details beside the list
+    app.wide_layout = app.ui.split_view && chunks[1].width >= SPLIT_MIN_WIDTH;
+    let (list_area, detail_area) = if app.wide_layout {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(chunks[1]);
+        (panes[0], Some(panes[1]))
+    } else {
+        (chunks[1], None)
+    };
+
+    render_feed_list(f, list_area, app);
+
+    if let Some(area) = detail_area {
+        render_detail_pane(f, area, app.selected_story(), &app.detail, &app.theme);
+    }
+
+    // Render footer with instructions
+    let footer: &[(&str, Option<Action>)] = if app.filtering {
+        FILTER_FOOTER
+    } else {
+        &[
+            ("↑↓ Navigate", None),
+            ("Tab Feed", Some(Action::NextFeed)),
+            ("Enter Read", Some(Action::Read)),
+            ("C Comments", Some(Action::Comments)),
+            ("/ Filter", Some(Action::Filter)),
+            ("? Help", Some(Action::Help)),
+            ("Q Quit", Some(Action::Quit)),
+        ]
+    };
+    app.mouse.buttons = render_footer(f, chunks[2], footer);
+}
+
+/// Renders the story list of the current screen, a feed or a user's submissions,
+/// or a message in its place while it is loading or has nothing to show
+fn render_feed_list(f: &mut Frame, area: Rect, app: &mut App) {
+    let Some(view) = app.story_list() else {
+        return;
+    };
+
+    // A failed fetch is reported above whatever the list still holds
+    let area = match &view.error {
+        Some(error) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)])
+                .split(area);
+            let banner = Paragraph::new(format!("❌ {} • R retries", error))
+                .style(Style::default().fg(Color::White))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Red))
+                        .title(format!("Could not load {}", view.description()))
+                        .title_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+                );
+            f.render_widget(banner, chunks[0]);
+            chunks[1]
+        }
+        None => area,
+    };
+
+    let filter_title = filter_summary(&view.filter, app.filtering);
+    let rows = view.rows();
+    if rows.is_empty() {
+        let message = if view.loading && view.feed.is_some() {
+            format!(
+                "{} Loading {}... {}%",
+                spinner(),
+                view.description(),
+                view.progress
+            )
+        } else if view.loading {
+            format!("Loading {}...", view.description())
+        } else if !view.filter.is_empty() && !view.stories.is_empty() {
+            format!("No loaded {} match \"{}\"", view.description(), view.filter)
+        } else if view.stories.is_empty() && view.failed > 0 {
+            format!("None of the {} could be loaded", view.description())
+        } else if !view.stories.is_empty() {
+            format!(
+                "All {} loaded {} are hidden by the killfile • H reveals them",
+                view.stories.len(),
+                view.description()
+            )
+        } else {
+            format!("No {} available", view.description())
+        };
+        let empty_msg = Paragraph::new(message)
+            .style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::White))
+                    .title(format!("{}{}", view.label(), filter_title)),
+            );
+        f.render_widget(empty_msg, area);
+        return;
+    }
+
+    // Create list items for each story shown, keeping its rank in the list
+    let words = filter::words(&view.filter);
+    let mut items: Vec<ListItem> = rows
+        .iter()
+        .map(|&index| {
+            let story = &view.stories[index];
+            // New stories and the killfile only concern feeds
+            let (new, hidden) = match view.feed {
+                Some(feed) => (
+                    app.history.is_new(feed, story.id),
+                    app.killfile.check(story).filter(|_| app.reveal_hidden),
+                ),
+                None => (false, None),
+            };
+            story_list_item(
+                index + 1,
+                story,
+                view.feed.and_then(Feed::title_prefix),
+                view.accent(),
+                &app.theme,
+                app.show_info,
+                Marks {
+                    change: view.change(story.id),
+                    new,
+                    hidden,
+                    matches: &words,
+                    ..app.marks(story)
+                },
+            )
+        })
+        .collect();
+
+    // Placeholder row while the next page is on its way
+    if view.loading_more {
+        items.push(ListItem::new(vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("    ⏳ Loading more {}...", view.noun()),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::ITALIC),
+            )),
+        ]));
+    }
+
+    let hidden = match view.feed {
+        Some(_) => hidden_summary(view.hidden.len(), app.reveal_hidden),
+        None => String::new(),
+    };
+    // Stories stay listed while the feed reloads
+    let refreshing = if view.loading && view.feed.is_some() {
+        format!(" • {} {}%", spinner(), view.progress)
+    } else {
+        String::new()
+    };
+    let border_color = view.border(&app.theme);
+    let heights: Vec<usize> = items.iter().map(ListItem::height).collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(border_color))
+                .title(format!(
+                    "{} {} ({}/{} of {}){}{}{}{}{}",
+                    view.icon(),
+                    view.label(),
+                    view.selected_row().map_or(0, |row| row + 1),
+                    rows.len(),
+                    view.ids.len(),
+                    refreshing,
+                    failed_summary(view.failed),
+                    sort_summary(view.sort),
+                    hidden,
+                    filter_title
+                ))
+                .title_style(
+                    Style::default()
+                        .fg(border_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .highlight_style(view.highlight_style(&app.theme))
+        .highlight_symbol("➤ ");
+
+    let mut list_state = ListState::default()
+        .with_offset(view.offset)
+        .with_selected(view.selected_row());
+    f.render_stateful_widget(list, area, &mut list_state);
+
+    let offset = list_state.offset();
+    if let Some(view) = app.story_list_mut() {
+        view.offset = offset;
+    }
+    app.mouse.list = Some(ListTarget {
+        area: area.inner(Margin::new(1, 1)),
+        offset,
+        heights,
+    });
+}
+
+/// Footer of a story list while its filter is typed
+const FILTER_FOOTER: &[(&str, Option<Action>)] = &[
+    ("Type to filter", None),
+    ("Enter Keep", None),
+    ("Esc Clear", None),
+];
+
+/// Suffix of a list title showing its filter, with a cursor while it is typed
+fn filter_summary(filter: &str, editing: bool) -> String {
+    if editing {
+        format!(" • 🔍 {}█", filter)
+    } else if !filter.is_empty() {
+        format!(" • 🔍 \"{}\" (n/N)", filter)
+    } else {
+        String::new()
+    }
+}
+
+/// Current frame of the loading spinner
+fn spinner() -> &'static str {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    SPINNER_FRAMES[(millis / 100) as usize % SPINNER_FRAMES.len()]
+}
+
+/// Suffix of a list title counting the items that failed to load
+fn failed_summary(failed: usize) -> String {
+    match failed {
+        0 => String::new(),
+        1 => " • ⚠ 1 item failed to load".to_string(),
+        _ => format!(" • ⚠ {} items failed to load", failed),
+    }
+}
+
+/// Suffix of a list title naming its order, unless it is the feed's own ranking
+fn sort_summary(sort: SortMode) -> String {
+    match sort {
+        SortMode::Rank => String::new(),
+        _ => format!(" • by {}", sort.label()),
+    }
+}
+
+/// Suffix of a list title telling how many stories the killfile hides
+fn hidden_summary(hidden: usize, revealed: bool) -> String {
+    if revealed {
+        " • hidden shown".to_string()
+    } else if hidden > 0 {
+        format!(" • {} hidden", hidden)
+    } else {
+        String::new()
+    }
+}
+
+/// Renders the detail pane for the selected story: full title, link, stats, post
+/// text and a preview of the top comments
+fn render_detail_pane(
+    f: &mut Frame,
+    area: ratatui::layout::Rect,
+    story: Option<&Item>,
+    detail: &DetailView,
+    theme: &Theme,
+) {
+    let width = area.width.saturating_sub(4) as usize;
+    let mut lines: Vec<Line> = Vec::new();
+
+    if let Some(story) = story {
+        lines.push(Line::from(Span::styled(
+            story.display_title(),
+            Style::default()
+                .fg(theme.title)
+                .add_modifier(Modifier::BOLD),
+        )));
+        if let Some(url) = story.url.as_deref().filter(|url| !url.is_empty()) {
+            lines.push(Line::from(Span::styled(
+                url.to_string(),
+                Style::default()
+                    .fg(theme.url)
+                    .add_modifier(Modifier::ITALIC),
+            )));
+        }
+        lines.push(Line::from(""));
+
+        let separator = || Span::styled(" │ ", Style::default().fg(theme.muted));
+        let mut stats = Vec::new();
+        if let Some(score) = story.score {
+            stats.push(Span::styled(
+                format!("▲ {} points", score),
+                Style::default()
+                    .fg(theme.score)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            stats.push(separator());
+        }
+        stats.push(Span::styled(
+            format!("👤 {}", story.author()),
+            Style::default().fg(theme.author),
+        ));
+        stats.push(separator());
+        stats.push(Span::styled(
+            format!(
+                "🕒 {} ago ({})",
+                time_ago(story.time),
+                format_date(story.time)
+            ),
+            Style::default().fg(theme.time),
+        ));
+        if let Some(count) = story.descendants {
+            stats.push(separator());
+            stats.push(Span::styled(
+                format!("💬 {} comments", count),
+                Style::default().fg(theme.comments),
+            ));
+        }
+        lines.push(Line::from(stats));
+
+        if let Some(text) = story.rich_text() {
+            lines.push(Line::from(""));
+            lines.extend(markup::layout(&text, width));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Top comments",
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )));
+        if detail.loading {
+            lines.push(Line::from(Span::styled(
+                "Loading comments...",
+                Style::default().fg(Color::Yellow),
+            )));
+        } else if detail.comments.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "No comments yet",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        for comment in &detail.comments {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    comment.author().to_string(),
+                    Style::default()
+                        .fg(theme.author)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" • {} ago", time_ago(comment.time)),
+                    Style::default().fg(theme.time),
+                ),
+            ]));
+            // Long comments are cut short; the full thread is a keypress away
+            let mut body = markup::layout(&comment.rich_text().unwrap_or_default(), width);
+            if body.len() > 6 {
+                body.truncate(6);
+                body.push(Line::from(Span::styled(
+                    "…",
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            lines.extend(body);
+        }
+    }
+
+    // Markup is laid out to the pane width already; wrapping catches the title and
+    // stats line
+    let pane = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray))
+            .padding(ratatui::widgets::Padding::horizontal(1))
+            .title("🔎 Details")
+            .title_style(
+                Style::default()
+                    .fg(Color::Gray)
+                    .add_modifier(Modifier::BOLD),
+            ),
+    );
+    f.render_widget(pane, area);
+}
+
+/// Markers drawn on a story's list row
+#[derive(Debug, Default, Clone, Copy)]
+struct Marks<'a> {
+    /// Recent live change to the rank, score or comment count
+    change: Option<&'a Change>,
+    /// Whether the story is bookmarked
+    saved: bool,
+    /// Whether the story was opened before
+    visited: bool,
+    /// Whether the story was not in the feed at the previous visit
+    new: bool,
+    /// Comments added since the story was last opened
+    new_comments: u32,
+    /// Why the killfile would hide the story, shown while hidden stories are revealed
+    hidden: Option<Reason>,
+    /// Lowercase words of the list filter, highlighted in the title, domain and author
+    matches: &'a [String],
+}
+
+/// Builds the list row for a story-like item
+fn story_list_item<'a>(
+    rank: usize,
+    story: &'a Item,
+    prefix: Option<&'static str>,
+    accent: Color,
+    theme: &Theme,
+    show_info: bool,
+    marks: Marks,
+) -> ListItem<'a> {
+    let mut content = story_lines(rank, story, prefix, accent, theme, show_info, marks);
+    // Empty line for spacing
+    content.push(Line::from(""));
+    ListItem::new(content).style(Style::default())
+}
+
+/// Lines of a story's list row, without the trailing spacing.
+///
+/// `prefix` is a title prefix such as "Show HN: " that is rendered as a colored
+/// tag in `accent` when the title starts with it. A recent live change is
+/// highlighted next to the rank, score and comment count.
+fn story_lines<'a>(
+    rank: usize,
+    story: &'a Item,
+    prefix: Option<&'static str>,
+    accent: Color,
+    theme: &Theme,
+    show_info: bool,
+    marks: Marks,
+) -> Vec<Line<'a>> {
+    let change = marks.change;
+    // Removed items stay in the list so the ranking has no gaps, but are dimmed
+    let removed = story.deleted || story.dead;
+    // Stories already opened are dimmed too, but less
+    let title_style = if removed {
+        Style::default().fg(theme.muted)
+    } else if marks.visited {
+        Style::default().fg(theme.visited)
+    } else {
+        Style::default()
+            .fg(theme.title)
+            .add_modifier(Modifier::BOLD)
+    };
+
+    // Format the URL display
+    let url_style = Style::default()
+        .fg(theme.url)
+        .add_modifier(Modifier::ITALIC);
+    let url_display = match story.domain() {
+        Some(domain) => {
+            let mut spans = vec![Span::styled(" (", url_style)];
+            spans.extend(filter::highlight(
+                domain.to_string(),
+                marks.matches,
+                url_style,
+                MATCH_STYLE,
+            ));
+            spans.push(Span::styled(")", url_style));
+            spans
+        }
+        None => Vec::new(),
+    };
+
+    // Format time
+    let time_str = time_ago(story.time);
+
+    // Title line with rank number, and tags for the item kind and the feed
+    let mut title_line = vec![Span::styled(
+        format!("{:2}. ", rank),
+        Style::default()
+            .fg(theme.muted)
+            .add_modifier(Modifier::BOLD),
+    )];
+    match change {
+        Some(change) if change.entered => {
+            title_line.push(Span::styled("★ ", Style::default().fg(Color::Yellow)));
+        }
+        Some(change) if change.moved != 0 => {
+            let (arrow, color) = if change.moved > 0 {
+                ("↑", Color::Green)
+            } else {
+                ("↓", Color::Red)
+            };
+            title_line.push(Span::styled(
+                format!("{}{} ", arrow, change.moved.abs()),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ));
+        }
+        _ => {}
+    }
+    if marks.new {
+        title_line.push(Span::styled(
+            "new ",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    if marks.saved {
+        title_line.push(Span::raw("🔖 "));
+    }
+    if let Some(reason) = marks.hidden {
+        title_line.push(Span::styled(
+            format!("[hidden: {}] ", reason.label()),
+            Style::default().fg(theme.muted),
+        ));
+    }
+    if story.dead {
+        title_line.push(Span::styled("[dead] ", Style::default().fg(Color::Red)));
+    }
+    if let Some(tag) = story.kind_tag() {
+        title_line.push(Span::styled(
+            tag,
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let mut title = story.display_title();
+    if let Some(prefix) = prefix {
+        if let Some(rest) = title.strip_prefix(prefix) {
+            title_line.push(Span::styled(
+                prefix,
+                Style::default().fg(accent).add_modifier(Modifier::BOLD),
+            ));
+            title = rest.to_string();
+        }
+    }
+    title_line.extend(filter::highlight(
+        title,
+        marks.matches,
+        title_style,
+        MATCH_STYLE,
+    ));
+    title_line.extend(url_display);
+    if marks.new_comments > 0 {
+        title_line.push(Span::styled(
+            format!(" 💬+{}", marks.new_comments),
+            Style::default()
+                .fg(theme.comments)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // Create aligned content with proper spacing
+    let mut content = vec![
+        // Empty line for spacing
+        Line::from(""),
+        Line::from(title_line),
+    ];
+
+    // Conditionally add stats line if info is enabled
+    if show_info {
+        let separator = || Span::styled(" │ ", Style::default().fg(theme.muted));
+        let delta = |amount: i64| {
+            let color = if amount > 0 { Color::Green } else { Color::Red };
+            Span::styled(
+                format!(" {:+}", amount),
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(color)
+                    .add_modifier(Modifier::BOLD),
+            )
+        };
+        let mut stats = vec![Span::styled("    ", Style::default())]; // Indent to align with title
+        if let Some(score) = story.score {
+            stats.push(Span::styled(
+                format!("▲ {:3}", score),
+                Style::default()
+                    .fg(theme.score)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            if let Some(change) = change.filter(|change| change.score != 0) {
+                stats.push(delta(change.score));
+            }
+            stats.push(separator());
+        }
+        let author_style = Style::default().fg(theme.author);
+        stats.push(Span::styled("👤 ", author_style));
+        stats.extend(filter::highlight(
+            story.author().to_string(),
+            marks.matches,
+            author_style,
+            MATCH_STYLE,
+        ));
+        match story.kind {
+            ItemType::Story | ItemType::Poll => {
+                stats.push(separator());
+                stats.push(Span::styled(
+                    format!("💬 {:2}", story.descendants.unwrap_or(0)),
+                    Style::default().fg(theme.comments),
+                ));
+                if let Some(change) = change.filter(|change| change.comments != 0) {
+                    stats.push(delta(change.comments));
+                }
+            }
+            ItemType::Comment => {
+                if let Some(parent) = story.parent {
+                    stats.push(separator());
+                    stats.push(Span::styled(
+                        format!("↳ reply to #{}", parent),
+                        Style::default().fg(theme.comments),
+                    ));
+                }
+            }
+            ItemType::PollOpt => {
+                if let Some(poll) = story.poll {
+                    stats.push(separator());
+                    stats.push(Span::styled(
+                        format!("📊 poll #{}", poll),
+                        Style::default().fg(theme.comments),
+                    ));
+                }
+            }
+            ItemType::Job | ItemType::Unknown => {}
+        }
+        stats.push(separator());
+        stats.push(Span::styled(
+            format!("🕒 {}", time_str),
+            Style::default().fg(theme.time),
+        ));
+        content.push(Line::from(stats));
+    }
+    content
+}
+
+/// Renders the threaded comment reader
+fn render_comments_screen(f: &mut Frame, app: &mut App) {
+    let view = &app.comments;
+    let poll_height = if view.poll_options.is_empty() {
+        0
+    } else {
+        view.poll_options.len() as u16 + 2
+    };
+
+    // The post's own text, capped so the discussion stays visible
+    let post = view
+        .story
+        .as_ref()
+        .and_then(|story| story.rich_text())
+        .map(|text| markup::layout(&text, f.area().width.saturating_sub(4) as usize))
+        .unwrap_or_default();
+    let post_height = if post.is_empty() {
+        0
+    } else {
+        (post.len() as u16 + 2).min(f.area().height / 3)
+    };
+    let post_truncated = post.len() as u16 + 2 > post_height;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),           // Story title
+            Constraint::Length(post_height), // Post text, if any
+            Constraint::Length(poll_height), // Poll options, if any
+            Constraint::Min(0),              // Comment tree
+            Constraint::Length(3),           // Footer
+        ])
+        .split(f.area());
+
+    let story_title = view
+        .story
+        .as_ref()
+        .map(|s| s.display_title())
+        .unwrap_or_default();
+    let title = Paragraph::new(format!("💬 {}", story_title))
+        .style(
             Style::default()
                 .fg(Color::White)
+                .bg(Color::Blue)
                 .add_modifier(Modifier::BOLD),
         )
         .alignment(Alignment::Center)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Gray))
-                .title("Controls"),
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+    f.render_widget(title, chunks[0]);
+
+    if !post.is_empty() {
+        let post = Paragraph::new(post).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::White))
+                .padding(ratatui::widgets::Padding::horizontal(1))
+                .title(if post_truncated {
+                    "📝 Post (P to read in full)"
+                } else {
+                    "📝 Post"
+                }),
+        );
+        f.render_widget(post, chunks[1]);
+    }
+
+    if !view.poll_options.is_empty() {
+        let lines: Vec<Line> = view
+            .poll_options
+            .iter()
+            .map(|option| {
+                Line::from(vec![
+                    Span::styled(
+                        format!(" ▲ {:4} ", option.score.unwrap_or(0)),
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(option.display_title(), Style::default().fg(Color::White)),
+                ])
+            })
+            .collect();
+        let poll = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::White))
+                .title("📊 Poll"),
         );
-    f.render_widget(instructions, chunks[3]);
+        f.render_widget(poll, chunks[2]);
+    }
+
+    let chunks = [chunks[0], chunks[3], chunks[4]];
+    if view.entries.is_empty() {
+        let (message, color) = match &view.error {
+            _ if view.loading => ("Loading comments...".to_string(), Color::Yellow),
+            Some(error) => (format!("❌ {} • R retries", error), Color::Red),
+            None => ("No comments yet".to_string(), Color::Yellow),
+        };
+        let empty_msg = Paragraph::new(message)
+            .style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::White))
+                    .title("Comments"),
+            );
+        f.render_widget(empty_msg, chunks[1]);
+    } else {
+        // Rough text width: borders, highlight symbol and a little slack
+        let text_width = chunks[1].width.saturating_sub(6) as usize;
+        let depth_colors = [
+            Color::Cyan,
+            Color::Green,
+            Color::Yellow,
+            Color::Magenta,
+            Color::Blue,
+            Color::Red,
+        ];
+
+        let visible = view.visible();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|&index| {
+                let entry = &view.entries[index];
+                let comment = &entry.item;
+                let guide_color = depth_colors[entry.depth % depth_colors.len()];
+                let indent = "│ ".repeat(entry.depth);
+
+                let collapsed = view.collapsed.contains(&index);
+                let marker = if entry.descendants == 0 {
+                    "•"
+                } else if collapsed {
+                    "▸"
+                } else {
+                    "▾"
+                };
+                let author = comment.author();
+
+                let mut header = vec![
+                    Span::styled(indent.clone(), Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("{} ", marker), Style::default().fg(guide_color)),
+                    Span::styled(
+                        author.to_string(),
+                        Style::default()
+                            .fg(Color::Magenta)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" • {} ago", time_ago(comment.time)),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                ];
+                if collapsed {
+                    header.push(Span::styled(
+                        format!(" ({} hidden)", entry.descendants),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+
+                let mut content = vec![Line::from(header)];
+                if !collapsed {
+                    let width = text_width.saturating_sub(indent.chars().count() + 2);
+                    let body = if comment.deleted {
+                        vec![Line::from("[deleted]")]
+                    } else if comment.dead {
+                        vec![Line::from("[dead]")]
+                    } else {
+                        markup::layout(&entry.text, width)
+                    };
+                    for line in body {
+                        let mut spans = vec![Span::styled(
+                            format!("{}  ", indent),
+                            Style::default().fg(Color::DarkGray),
+                        )];
+                        spans.extend(line.spans);
+                        content.push(Line::from(spans));
+                    }
+                }
+                content.push(Line::from(""));
+                ListItem::new(content)
+            })
+            .collect();
+
+        let position = visible
+            .iter()
+            .position(|&i| i == view.selected)
+            .unwrap_or(0);
+        let heights: Vec<usize> = items.iter().map(ListItem::height).collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::White))
+                    .title(comments_title(view))
+                    .title_style(
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+            )
+            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_symbol("➤ ");
+
+        let mut state = ListState::default();
+        state.select(Some(position));
+
+        f.render_stateful_widget(list, chunks[1], &mut state);
+        app.mouse.list = Some(ListTarget {
+            area: chunks[1].inner(Margin::new(1, 1)),
+            offset: state.offset(),
+            heights,
+        });
+    }
+
+    app.mouse.buttons = render_footer(
+        f,
+        chunks[2],
+        &[
+            ("↑↓ Navigate", None),
+            ("←→ Parent/Reply", None),
+            ("Space Collapse", Some(Action::Collapse)),
+            ("? Help", Some(Action::Help)),
+            (": Commands", Some(Action::CommandPalette)),
+            ("Esc Back", Some(Action::Back)),
+        ],
+    );
 }
 
-/// Renders the main stories screen
-fn render_stories_screen(f: &mut Frame, app: &mut App) {
-    // Split the screen into header, stories, and footer sections
+/// Title of the comment list, with the status of a reload
+fn comments_title(view: &CommentView) -> Line<'static> {
+    let mut title = vec![Span::raw(format!(
+        "💬 Comments ({}/{}){}",
+        view.selected + 1,
+        view.entries.len(),
+        failed_summary(view.failed)
+    ))];
+    if view.loading {
+        title.push(Span::raw(" • reloading..."));
+    } else if let Some(error) = &view.error {
+        title.push(Span::styled(
+            format!(" • ❌ {} • R retries", error),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    Line::from(title)
+}
+
+/// Renders the article reader
+fn render_reader_screen(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3), // Header
-            Constraint::Min(0),    // Stories list
+            Constraint::Length(3), // Article title
+            Constraint::Min(0),    // Article text
             Constraint::Length(3), // Footer
         ])
         .split(f.area());
 
-    // Render the header with enhanced styling
-    let title = Paragraph::new("📰 Hacker News Top Stories")
+    let view = &mut app.reader;
+    let story = view.story.as_ref();
+    let title = view
+        .article
+        .as_ref()
+        .and_then(|article| article.title.clone())
+        .or_else(|| story.map(|story| story.display_title()))
+        .unwrap_or_default();
+    let domain = story.and_then(|story| story.domain()).unwrap_or_default();
+    let header = Paragraph::new(format!("📖 {}", title))
         .style(
             Style::default()
                 .fg(Color::White)
@@ -393,136 +3624,174 @@ fn render_stories_screen(f: &mut Frame, app: &mut App) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::Cyan))
-                .title_style(
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD),
-                ),
+                .title(domain),
         );
-    f.render_widget(title, chunks[0]);
+    f.render_widget(header, chunks[0]);
 
-    if app.stories.is_empty() {
-        let empty_msg = Paragraph::new("No stories available")
-            .style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+    match (&view.article, &view.error) {
+        (Some(article), _) => {
+            // Leave a column of padding on each side of the text
+            let width = chunks[1].width.saturating_sub(4) as usize;
+            let height = chunks[1].height.saturating_sub(2) as usize;
+            let lines = markup::layout(&article.text, width);
+            view.page_height = height;
+            view.scroll = view.scroll.min(lines.len().saturating_sub(height));
+            let percent = if lines.len() <= height {
+                100
+            } else {
+                (view.scroll + height) * 100 / lines.len()
+            };
+
+            let text = Paragraph::new(lines)
+                .scroll((view.scroll.min(u16::MAX as usize) as u16, 0))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::White))
+                        .padding(ratatui::widgets::Padding::horizontal(1))
+                        .title(format!("📖 Reader ({}%)", percent))
+                        .title_style(
+                            Style::default()
+                                .fg(Color::White)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                );
+            f.render_widget(text, chunks[1]);
+        }
+        (None, error) => {
+            let (message, color) = match error {
+                Some(error) => (format!("❌ {}", error), Color::Red),
+                None => ("Loading article...".to_string(), Color::Yellow),
+            };
+            let message = Paragraph::new(message)
+                .style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::White))
+                        .title("📖 Reader"),
+                );
+            f.render_widget(message, chunks[1]);
+        }
+    }
+
+    app.mouse.buttons = render_footer(
+        f,
+        chunks[2],
+        &[
+            ("↑↓ Scroll", None),
+            ("Space/b Page", Some(Action::PageDown)),
+            ("C Comments", Some(Action::Comments)),
+            ("? Help", Some(Action::Help)),
+            (": Commands", Some(Action::CommandPalette)),
+            ("Esc Back", Some(Action::Back)),
+        ],
+    );
+}
+
+/// Renders the search prompt and its results
+fn render_search_screen(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Search prompt
+            Constraint::Min(0),    // Results list
+            Constraint::Length(3), // Footer
+        ])
+        .split(f.area());
+
+    let search = &app.search;
+    let accent = Color::Yellow;
+
+    // Render the prompt, with a cursor while it has focus
+    let cursor = if search.editing { "█" } else { "" };
+    let prompt = Paragraph::new(Line::from(vec![
+        Span::styled("🔍 ", Style::default().fg(accent)),
+        Span::styled(
+            format!("{}{}", search.input, cursor),
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(if search.editing { accent } else { Color::Gray }))
+            .title("Search Hacker News")
+            .title_style(Style::default().fg(accent).add_modifier(Modifier::BOLD)),
+    );
+    f.render_widget(prompt, chunks[0]);
+
+    if search.results.is_empty() {
+        let (message, color) = if let Some(error) = &search.error {
+            (format!("❌ {}", error), Color::Red)
+        } else if search.loading {
+            ("Searching...".to_string(), Color::Yellow)
+        } else if search.query.is_some() {
+            ("No results".to_string(), Color::Yellow)
+        } else {
+            (
+                "Type a query and press Enter. Filters: type:story|comment|all  points>N  \
+                 since:7d  after:YYYY-MM-DD  before:YYYY-MM-DD  sort:date"
+                    .to_string(),
+                Color::Gray,
             )
+        };
+        let empty_msg = Paragraph::new(message)
+            .style(Style::default().fg(color).add_modifier(Modifier::BOLD))
             .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(Color::White))
-                    .title("Stories"),
+                    .title("Results"),
             );
         f.render_widget(empty_msg, chunks[1]);
     } else {
-        // Create list items for each story with improved visual design
-        let items: Vec<ListItem> = app
-            .stories
+        let mut items: Vec<ListItem> = search
+            .results
             .iter()
             .enumerate()
             .map(|(index, story)| {
-                // Format the URL display
-                let url_display = if let Some(url) = &story.url {
-                    if !url.is_empty() {
-                        let domain = url.split('/').nth(2).unwrap_or("unknown");
-                        format!(" ({})", domain)
-                    } else {
-                        String::new()
-                    }
-                } else {
-                    String::new()
-                };
-
-                // Format time
-                let time_str = {
-                    use std::time::{SystemTime, UNIX_EPOCH};
-                    let now = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-                    let diff = now.saturating_sub(story.time);
-                    if diff < 3600 {
-                        format!("{}m", diff / 60)
-                    } else if diff < 86400 {
-                        format!("{}h", diff / 3600)
-                    } else {
-                        format!("{}d", diff / 86400)
-                    }
-                };
-
-                // Create aligned content with proper spacing
-                let mut content = vec![
-                    // Empty line for spacing
-                    Line::from(""),
-                    // Title line with rank number
-                    Line::from(vec![
-                        Span::styled(
-                            format!("{:2}. ", index + 1),
-                            Style::default()
-                                .fg(Color::DarkGray)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(
-                            &story.title,
-                            Style::default()
-                                .fg(Color::White)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(
-                            url_display,
-                            Style::default()
-                                .fg(Color::Blue)
-                                .add_modifier(Modifier::ITALIC),
-                        ),
-                    ]),
-                ];
-
-                // Conditionally add stats line if info is enabled
-                if app.show_info {
-                    content.push(Line::from(vec![
-                        Span::styled("    ", Style::default()), // Indent to align with title
-                        Span::styled(
-                            format!("▲ {:3}", story.score),
-                            Style::default()
-                                .fg(Color::Green)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
-                        Span::styled(
-                            format!("👤 {}", story.by),
-                            Style::default().fg(Color::Magenta),
-                        ),
-                        Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
-                        Span::styled(
-                            format!("💬 {:2}", story.descendants.unwrap_or(0)),
-                            Style::default().fg(Color::Cyan),
-                        ),
-                        Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
-                        Span::styled(
-                            format!("🕒 {}", time_str),
-                            Style::default().fg(Color::Yellow),
-                        ),
-                    ]));
-                }
-
-                // Empty line for spacing
-                content.push(Line::from(""));
-                ListItem::new(content).style(Style::default())
+                story_list_item(
+                    index + 1,
+                    story,
+                    None,
+                    accent,
+                    &app.theme,
+                    app.show_info,
+                    app.marks(story),
+                )
             })
             .collect();
 
-        // Render the stories list with enhanced styling
+        // Placeholder row while the next page is on its way
+        if search.loading {
+            items.push(ListItem::new(vec![
+                Line::from(""),
+                Line::from(Span::styled(
+                    "    ⏳ Loading more results...",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::ITALIC),
+                )),
+            ]));
+        }
+
+        let heights: Vec<usize> = items.iter().map(ListItem::height).collect();
         let list = List::new(items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(Color::White))
                     .title(format!(
-                        "📋 Stories ({}/{})",
-                        app.selected + 1,
-                        app.stories.len()
+                        "🔍 Results ({}/{})",
+                        search.selected + 1,
+                        search.results.len()
                     ))
                     .title_style(
                         Style::default()
@@ -532,492 +3801,1220 @@ fn render_stories_screen(f: &mut Frame, app: &mut App) {
             )
             .highlight_style(
                 Style::default()
-                    .bg(Color::Yellow)
+                    .bg(accent)
                     .fg(Color::Black)
                     .add_modifier(Modifier::BOLD),
             )
             .highlight_symbol("➤ ");
 
         let mut state = ListState::default();
-        state.select(Some(app.selected));
+        state.select(Some(search.selected));
 
         f.render_stateful_widget(list, chunks[1], &mut state);
+        app.mouse.list = Some(ListTarget {
+            area: chunks[1].inner(Margin::new(1, 1)),
+            offset: state.offset(),
+            heights,
+        });
     }
 
-    // Render footer with instructions
-    let info_status = if app.show_info { "ON" } else { "OFF" };
-    let footer_text = format!(
-        "↑↓ Navigate • Enter Open Link • I Info ({}) • S Show HN • R Refresh • Q Quit",
-        info_status
-    );
-    let footer = Paragraph::new(footer_text)
-        .style(
+    let footer: &[(&str, Option<Action>)] = if search.editing {
+        &[
+            ("Type to search", None),
+            ("Enter Search", None),
+            ("Esc Cancel", None),
+        ]
+    } else {
+        &[
+            ("↑↓ Navigate", None),
+            ("Enter Read", Some(Action::Read)),
+            ("C Comments", Some(Action::Comments)),
+            ("F Edit Query", Some(Action::EditQuery)),
+            ("? Help", Some(Action::Help)),
+            ("Esc Back", Some(Action::Back)),
+        ]
+    };
+    app.mouse.buttons = render_footer(f, chunks[2], footer);
+}
+
+/// Renders a user's profile and submissions
+fn render_user_screen(f: &mut Frame, app: &mut App) {
+    let view = &app.user;
+    let accent = Color::Magenta;
+
+    // Profile text wrapped to the pane, capped so the submissions stay visible
+    let text_width = f.area().width.saturating_sub(4) as usize;
+    let about = view
+        .user
+        .as_ref()
+        .and_then(|user| user.about.as_deref())
+        .map(|about| markup::layout(&markup::from_hn_html(about), text_width))
+        .unwrap_or_default();
+    let profile_height = (about.len() as u16 + 3).min(f.area().height / 3).max(3);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(profile_height + 2), // Profile
+            Constraint::Min(0),                     // Submissions
+            Constraint::Length(3),                  // Footer
+        ])
+        .split(f.area());
+
+    let mut profile = Vec::new();
+    if let Some(user) = &view.user {
+        profile.push(Line::from(vec![
+            Span::styled(
+                format!("⭐ {} karma", user.karma),
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("📅 joined {}", format_date(user.created)),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("📝 {} submissions", user.submitted.len()),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]));
+        if let Some(error) = &view.error {
+            // A failed reload leaves the earlier profile shown
+            profile.push(Line::from(Span::styled(
+                format!("❌ {}", error),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        profile.push(Line::from(""));
+        profile.extend(about);
+    } else if let Some(error) = &view.error {
+        profile.push(Line::from(Span::styled(
+            format!("❌ {}", error),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+    } else {
+        profile.push(Line::from(Span::styled(
+            "Loading profile...",
             Style::default()
-                .fg(Color::White)
-                .bg(Color::DarkGray)
+                .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Gray))
-                .title("Controls")
-                .title_style(
-                    Style::default()
-                        .fg(Color::Gray)
-                        .add_modifier(Modifier::BOLD),
-                ),
-        );
-    f.render_widget(footer, chunks[2]);
+        )));
+    }
+    let profile = Paragraph::new(profile).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(accent))
+            .title(format!("👤 {}", view.name))
+            .title_style(Style::default().fg(accent).add_modifier(Modifier::BOLD)),
+    );
+    f.render_widget(profile, chunks[0]);
+
+    render_feed_list(f, chunks[1], app);
+
+    let footer: &[(&str, Option<Action>)] = if app.filtering {
+        FILTER_FOOTER
+    } else {
+        &[
+            ("↑↓ Navigate", None),
+            ("Enter Read", Some(Action::Read)),
+            ("C Comments", Some(Action::Comments)),
+            ("/ Filter", Some(Action::Filter)),
+            ("? Help", Some(Action::Help)),
+            ("Esc Back", Some(Action::Back)),
+        ]
+    };
+    app.mouse.buttons = render_footer(f, chunks[2], footer);
 }
 
-/// Renders the Show HN screen
-fn render_show_hn_screen(f: &mut Frame, app: &mut App) {
-    // Split the screen into header, stories, and footer sections
+/// Renders the bookmarked stories with their tags and notes
+fn render_saved_screen(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3), // Header
-            Constraint::Min(0),    // Stories list
+            Constraint::Length(3), // Filter prompt
+            Constraint::Min(0),    // Bookmarks
             Constraint::Length(3), // Footer
         ])
         .split(f.area());
 
-    // Render the header with Show HN styling
-    let title = Paragraph::new("🚀 Show HN")
-        .style(
+    let view = &app.saved;
+    let accent = Color::LightYellow;
+
+    // The prompt line shows the filter, or the tags or note being edited
+    let (label, text) = match view.prompt {
+        Some(SavedPrompt::Tags) => ("Tags: ", format!("{}█", view.input)),
+        Some(SavedPrompt::Note) => ("Note: ", format!("{}█", view.input)),
+        Some(SavedPrompt::Filter) => ("🔍 ", format!("{}█", view.filter)),
+        None => ("🔍 ", view.filter.clone()),
+    };
+    let mut prompt_line = vec![
+        Span::styled(label, Style::default().fg(accent)),
+        Span::styled(
+            text,
             Style::default()
                 .fg(Color::White)
-                .bg(Color::Magenta)
                 .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Magenta))
-                .title_style(
-                    Style::default()
-                        .fg(Color::Magenta)
-                        .add_modifier(Modifier::BOLD),
-                ),
-        );
-    f.render_widget(title, chunks[0]);
+        ),
+    ];
+    if let Some(error) = view.error.as_ref().filter(|_| view.prompt.is_none()) {
+        prompt_line.push(Span::styled(
+            format!("  ❌ {}", error),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    let prompt = Paragraph::new(Line::from(prompt_line)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(if view.prompt.is_some() {
+                accent
+            } else {
+                Color::Gray
+            }))
+            .title("🔖 Saved Stories")
+            .title_style(Style::default().fg(accent).add_modifier(Modifier::BOLD)),
+    );
+    f.render_widget(prompt, chunks[0]);
 
-    if app.show_stories.is_empty() {
-        let empty_msg = Paragraph::new("No Show HN stories available")
+    let matches = app.saved_matches();
+    if matches.is_empty() {
+        let message = if app.bookmarks.entries().is_empty() {
+            "No saved stories yet. Press b on a story to save it.".to_string()
+        } else {
+            format!("No saved stories match \"{}\"", view.filter)
+        };
+        let empty_msg = Paragraph::new(message)
             .style(
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(Color::Gray)
                     .add_modifier(Modifier::BOLD),
             )
             .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(Color::White))
-                    .title("Show HN Stories"),
+                    .title("Saved"),
             );
         f.render_widget(empty_msg, chunks[1]);
     } else {
-        // Create list items for each Show HN story
-        let items: Vec<ListItem> = app
-            .show_stories
+        let theme = &app.theme;
+        let items: Vec<ListItem> = matches
             .iter()
             .enumerate()
-            .map(|(index, story)| {
-                // Format the URL display
-                let url_display = if let Some(url) = &story.url {
-                    if !url.is_empty() {
-                        let domain = url.split('/').nth(2).unwrap_or("unknown");
-                        format!(" ({})", domain)
+            .map(|(rank, &index)| {
+                let entry = &app.bookmarks.entries()[index];
+                let mut lines = story_lines(
+                    rank + 1,
+                    &entry.item,
+                    None,
+                    accent,
+                    theme,
+                    app.show_info,
+                    Marks {
+                        saved: false,
+                        ..app.marks(&entry.item)
+                    },
+                );
+
+                // When it was saved, its tags and its note
+                let separator = || Span::styled(" │ ", Style::default().fg(theme.muted));
+                let mut saved = vec![
+                    Span::raw("    "),
+                    Span::styled(
+                        format!("🔖 saved {} ago", time_ago(entry.saved_at)),
+                        Style::default().fg(theme.time),
+                    ),
+                ];
+                if !entry.tags.is_empty() {
+                    let tags: Vec<String> =
+                        entry.tags.iter().map(|tag| format!("#{}", tag)).collect();
+                    saved.push(separator());
+                    saved.push(Span::styled(
+                        tags.join(" "),
+                        Style::default().fg(accent).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                if let Some(note) = &entry.note {
+                    saved.push(separator());
+                    saved.push(Span::styled(
+                        note.clone(),
+                        Style::default()
+                            .fg(theme.muted)
+                            .add_modifier(Modifier::ITALIC),
+                    ));
+                }
+                lines.push(Line::from(saved));
+                lines.push(Line::from(""));
+                ListItem::new(lines)
+            })
+            .collect();
+
+        let heights: Vec<usize> = items.iter().map(ListItem::height).collect();
+        let title = if view.filter.is_empty() {
+            format!("Saved ({}/{})", view.selected + 1, matches.len())
+        } else {
+            format!(
+                "Saved ({}/{} of {})",
+                view.selected + 1,
+                matches.len(),
+                app.bookmarks.entries().len()
+            )
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::White))
+                    .title(title)
+                    .title_style(
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(accent)
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("➤ ");
+
+        let mut state = ListState::default();
+        state.select(Some(view.selected));
+
+        f.render_stateful_widget(list, chunks[1], &mut state);
+        app.mouse.list = Some(ListTarget {
+            area: chunks[1].inner(Margin::new(1, 1)),
+            offset: state.offset(),
+            heights,
+        });
+    }
+
+    let footer: &[(&str, Option<Action>)] = match app.saved.prompt {
+        Some(SavedPrompt::Filter) => &[
+            ("Type to filter", None),
+            ("Enter Done", None),
+            ("Esc Clear", None),
+        ],
+        Some(_) => &[
+            ("Type to edit", None),
+            ("Enter Save", None),
+            ("Esc Cancel", None),
+        ],
+        None => &[
+            ("↑↓ Navigate", None),
+            ("Enter Read", Some(Action::Read)),
+            ("/ Filter", Some(Action::Filter)),
+            ("# Tags", Some(Action::EditTags)),
+            ("E Note", Some(Action::EditNote)),
+            ("D Remove", Some(Action::Bookmark)),
+            ("Esc Back", Some(Action::Back)),
+        ],
+    };
+    app.mouse.buttons = render_footer(f, chunks[2], footer);
+}
+
+// ===== MAIN APPLICATION LOOP =====
+
+/// Runs the main application loop, handling user input and rendering the UI
+async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    // Start loading stories in the background
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+    let feed = app.feed;
+    load_feed(app, &tx, feed, false);
+
+    // Live subscription for the feed currently shown
+    let mut live: Option<(Feed, JoinHandle<()>)> = None;
+
+    loop {
+        // Follow the current feed live, resubscribing whenever it changes
+        let can_follow = app.ui.live_updates && !app.fetch.cache.offline;
+        if can_follow && live.as_ref().map(|(feed, _)| *feed) != Some(app.feed) {
+            if let Some((_, task)) = live.take() {
+                task.abort();
+            }
+            app.live = LiveStatus::Connecting;
+            live = Some((app.feed, spawn_live_updates(&tx, app.feed, &app.fetch)));
+        }
+
+        // Handle background messages
+        while let Ok(msg) = rx.try_recv() {
+            match msg {
+                AppMessage::Progress(feed, generation, progress) => {
+                    app.update_loading_progress(feed, generation, progress);
+                }
+                AppMessage::StoriesLoaded(feed, generation, ids, stories) => {
+                    app.set_stories(feed, generation, ids, stories);
+                    // Keep loading while the killfile leaves too few stories to scroll
+                    request_more_items(app, &tx);
+                }
+                AppMessage::FeedFailed(feed, generation, error) => {
+                    app.set_feed_error(feed, generation, error);
+                }
+                AppMessage::MoreStoriesLoaded(feed, generation, start, stories) => {
+                    app.append_stories(feed, generation, start, stories);
+                    request_more_items(app, &tx);
+                }
+                AppMessage::CommentsLoaded(story, poll_options, tree, failed) => {
+                    app.set_comments(*story, poll_options, tree, failed);
+                }
+                AppMessage::SearchLoaded(generation, page, results) => {
+                    app.add_search_results(generation, page, results);
+                }
+                AppMessage::SearchFailed(generation, error) => {
+                    app.set_search_error(generation, error);
+                }
+                AppMessage::UserLoaded(user, submissions) => {
+                    app.set_user(user, submissions);
+                }
+                AppMessage::UserFailed(name, error) => {
+                    app.set_user_error(name, error);
+                }
+                AppMessage::SubmissionsLoaded(name, start, items) => {
+                    app.append_submissions(name, start, items);
+                }
+                AppMessage::DetailLoaded(story_id, comments) => {
+                    app.set_detail(story_id, comments);
+                }
+                AppMessage::Live(feed, event) => {
+                    let ids = app.handle_live(feed, event);
+                    if !ids.is_empty() {
+                        spawn_live_items(&tx, feed, ids, &app.fetch);
+                    }
+                }
+                AppMessage::LiveItems(feed, items) => {
+                    app.apply_live_items(feed, items);
+                }
+                AppMessage::ArticleLoaded(url, article) => {
+                    app.set_article(url, article);
+                }
+                AppMessage::ArticleFailed(url, error) => {
+                    app.set_article_error(url, error);
+                }
+                AppMessage::CommentsFailed(story_id, error) => {
+                    app.set_comments_error(story_id, error);
+                }
+                AppMessage::HistoryFailed(error) => app.set_notice(error),
+            }
+        }
+
+        // Write the reading history off the UI thread once changes settle
+        if let Some(snapshot) = app.history.pending_save() {
+            spawn_history_save(&tx, snapshot);
+        }
+
+        // Render the current UI state
+        terminal.draw(|f| ui(f, app))?;
+
+        // Keep the detail pane's comment preview on the selected story
+        if let Some((story_id, ids)) = app.request_detail() {
+            spawn_detail_fetch(&tx, story_id, ids, &app.fetch);
+        }
+
+        // Poll for user input with a timeout
+        if event::poll(Duration::from_millis(100))? {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                Event::Mouse(mouse) => {
+                    if handle_mouse(app, mouse, &tx) {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            // Only handle key press events, ignore key release
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            // Overlays and the search prompt take free text, so their keys are fixed
+            if app.overlay != Overlay::None {
+                if handle_overlay_key(app, key, &tx) {
+                    return Ok(());
+                }
+                continue;
+            }
+            if app.state == AppState::Search && app.search.editing {
+                edit_search(app, key.code, &tx);
+                continue;
+            }
+            if app.state == AppState::Saved && app.saved.prompt.is_some() {
+                edit_saved(app, key.code);
+                continue;
+            }
+            if app.filtering && app.story_list().is_some() {
+                edit_filter(app, key.code);
+                continue;
+            }
+            let mode = app.mode();
+            if let Press::Action(action) = app.keymap.press(mode, key) {
+                if perform(app, action, &tx) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Handles a key while the help overlay or command palette is open.
+///
+/// Returns true if the app should quit.
+fn handle_overlay_key(app: &mut App, key: KeyEvent, tx: &UnboundedSender<AppMessage>) -> bool {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let entries = palette::entries(&app.keymap, app.mode());
+    match &mut app.overlay {
+        Overlay::None => {}
+        Overlay::Help { scroll } => match key.code {
+            KeyCode::Down | KeyCode::Char('j') => *scroll = scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            _ => app.overlay = Overlay::None,
+        },
+        Overlay::Palette(palette) => {
+            let matches = palette.matches(&entries);
+            match key.code {
+                KeyCode::Esc => app.overlay = Overlay::None,
+                KeyCode::Enter => {
+                    let entry = matches.get(palette.selected).map(|&index| &entries[index]);
+                    app.overlay = Overlay::None;
+                    match entry.map(|entry| (entry.action, entry.elsewhere)) {
+                        Some((action, None)) => return perform(app, action, tx),
+                        Some((action, Some(mode))) => app.set_notice(format!(
+                            "{} is only available on the {} screen",
+                            action.description(),
+                            mode.name()
+                        )),
+                        None => {}
+                    }
+                }
+                KeyCode::Down | KeyCode::Tab => palette.next(matches.len()),
+                KeyCode::Char('n') if ctrl => palette.next(matches.len()),
+                KeyCode::Up | KeyCode::BackTab => palette.previous(matches.len()),
+                KeyCode::Char('p') if ctrl => palette.previous(matches.len()),
+                KeyCode::Backspace => {
+                    // Backspace on an empty prompt closes the palette
+                    if palette.input.pop().is_none() {
+                        app.overlay = Overlay::None;
                     } else {
-                        String::new()
+                        palette.selected = 0;
                     }
-                } else {
-                    String::new()
+                }
+                KeyCode::Char(c) if !ctrl => {
+                    palette.input.push(c);
+                    palette.selected = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+/// Handles a key typed into the search prompt
+fn edit_search(app: &mut App, code: KeyCode, tx: &UnboundedSender<AppMessage>) {
+    match code {
+        KeyCode::Esc => {
+            // Back to the results, or out of search if there are none
+            if app.search.results.is_empty() {
+                app.close_search();
+            } else {
+                app.search.editing = false;
+            }
+        }
+        KeyCode::Enter => {
+            if let Some((query, generation)) = app.submit_search() {
+                spawn_search(tx, query, generation, 0, &app.fetch);
+            }
+        }
+        KeyCode::Backspace => {
+            app.search.input.pop();
+        }
+        KeyCode::Char(c) => {
+            app.search.input.push(c);
+        }
+        _ => {}
+    }
+}
+
+/// Handles a key typed into the filter of the story list being browsed
+fn edit_filter(app: &mut App, code: KeyCode) {
+    let Some(list) = app.story_list_mut() else {
+        return;
+    };
+    let mut filter = list.filter.clone();
+    match code {
+        // Escape drops the filter, Enter keeps it and returns to the list
+        KeyCode::Esc => {
+            filter.clear();
+            app.filtering = false;
+        }
+        KeyCode::Enter => {
+            if filter.trim().is_empty() {
+                filter.clear();
+            }
+            app.filtering = false;
+        }
+        KeyCode::Backspace => {
+            filter.pop();
+        }
+        KeyCode::Char(c) => filter.push(c),
+        _ => return,
+    }
+    if let Some(list) = app.story_list_mut() {
+        list.set_filter(filter);
+    }
+}
+
+/// Handles a key typed into a prompt of the Saved view
+fn edit_saved(app: &mut App, code: KeyCode) {
+    let filtering = app.saved.prompt == Some(SavedPrompt::Filter);
+    match code {
+        KeyCode::Esc => {
+            // Leaving the filter prompt clears the filter, other prompts are discarded
+            if filtering {
+                app.saved.filter.clear();
+            }
+            app.saved.prompt = None;
+            app.saved.input.clear();
+        }
+        KeyCode::Enter if filtering => app.saved.prompt = None,
+        KeyCode::Enter => app.submit_saved_prompt(),
+        KeyCode::Backspace if filtering => {
+            app.saved.filter.pop();
+            app.saved.selected = 0;
+        }
+        KeyCode::Backspace => {
+            app.saved.input.pop();
+        }
+        KeyCode::Char(c) if filtering => {
+            app.saved.filter.push(c);
+            app.saved.selected = 0;
+        }
+        KeyCode::Char(c) => {
+            app.saved.input.push(c);
+        }
+        _ => {}
+    }
+}
+
+/// Handles a mouse event against the regions recorded by the last render.
+///
+/// Returns true if the app should quit.
+fn handle_mouse(app: &mut App, mouse: MouseEvent, tx: &UnboundedSender<AppMessage>) -> bool {
+    let browsing = matches!(
+        app.state,
+        AppState::Stories
+            | AppState::Search
+            | AppState::User
+            | AppState::Comments
+            | AppState::Reader
+            | AppState::Saved
+    );
+    let typing = (app.state == AppState::Search && app.search.editing)
+        || (app.state == AppState::Saved && app.saved.prompt.is_some())
+        || (app.filtering && app.story_list().is_some());
+    if !browsing || typing {
+        return false;
+    }
+    match mouse.kind {
+        // Overlays close on a click and otherwise ignore the mouse
+        MouseEventKind::Down(_) if app.overlay != Overlay::None => app.overlay = Overlay::None,
+        _ if app.overlay != Overlay::None => {}
+        MouseEventKind::ScrollDown if app.state == AppState::Reader => {
+            app.reader.scroll_down(WHEEL_LINES)
+        }
+        MouseEventKind::ScrollUp if app.state == AppState::Reader => {
+            app.reader.scroll_up(WHEEL_LINES)
+        }
+        MouseEventKind::ScrollDown => return perform(app, Action::Down, tx),
+        MouseEventKind::ScrollUp => return perform(app, Action::Up, tx),
+        MouseEventKind::Down(MouseButton::Left) => {
+            let (x, y) = (mouse.column, mouse.row);
+            let hit = |area: &Rect| area.contains((x, y).into());
+            if let Some(&(_, action)) = app.mouse.buttons.iter().find(|(area, _)| hit(area)) {
+                return perform(app, action, tx);
+            }
+            if let Some(&(_, feed)) = app.mouse.tabs.iter().find(|(area, _)| hit(area)) {
+                if app.switch_feed(feed) {
+                    load_feed(app, tx, feed, false);
+                }
+                return false;
+            }
+            let row = app.mouse.list.as_ref().and_then(|list| list.row_at(x, y));
+            let Some(row) = row.filter(|&row| app.select_row(row)) else {
+                return false;
+            };
+            request_more_items(app, tx);
+
+            // A second click on the same row opens it
+            let now = Instant::now();
+            let double = app.last_click.is_some_and(|(time, last)| {
+                last == row && now.duration_since(time) <= DOUBLE_CLICK_INTERVAL
+            });
+            if double {
+                app.last_click = None;
+                let action = match app.state {
+                    AppState::Comments => Action::Collapse,
+                    _ => Action::Read,
                 };
+                return perform(app, action, tx);
+            }
+            app.last_click = Some((now, row));
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Carries out a bound action on the current screen.
+///
+/// Returns true if the app should quit.
+fn perform(app: &mut App, action: Action, tx: &UnboundedSender<AppMessage>) -> bool {
+    match action {
+        Action::Quit => return true,
+        Action::Back => match app.state {
+            AppState::Search => app.close_search(),
+            AppState::User => app.close_user(),
+            AppState::Comments => app.close_comments(),
+            AppState::Reader => app.close_reader(),
+            AppState::Saved => app.close_saved(),
+            _ => {}
+        },
+        Action::Down if app.state == AppState::Reader => app.reader.scroll_down(1),
+        Action::Down => {
+            app.next();
+            request_more_items(app, tx);
+        }
+        Action::Up if app.state == AppState::Reader => app.reader.scroll_up(1),
+        Action::Up => app.previous(),
+        Action::Top if app.state == AppState::Reader => app.reader.scroll = 0,
+        Action::Top => app.select_first(),
+        Action::Bottom if app.state == AppState::Reader => app.reader.scroll = usize::MAX,
+        Action::Bottom => {
+            app.select_last();
+            request_more_items(app, tx);
+        }
+        Action::PageDown => {
+            let page = app.reader.page_height.saturating_sub(2).max(1);
+            app.reader.scroll_down(page);
+        }
+        Action::PageUp => {
+            let page = app.reader.page_height.saturating_sub(2).max(1);
+            app.reader.scroll_up(page);
+        }
+        Action::Read => {
+            // Read the article, or the discussion if there is no link
+            read_selected(app, tx);
+        }
+        Action::OpenBrowser => {
+            let url = match app.state {
+                AppState::Reader => app.reader.url(),
+                AppState::Comments => app.comments.story.as_ref().and_then(|s| s.url.as_deref()),
+                _ => app.selected_story().and_then(|s| s.url.as_deref()),
+            };
+            if let Some(url) = url.filter(|url| !url.is_empty()) {
+                let _ = open::that(url);
+                // Opening a listed story in the browser counts as visiting it
+                if let Some(story) = app.selected_story().cloned() {
+                    app.history.record_visit(&story);
+                }
+            }
+        }
+        Action::Comments => {
+            let story = match app.state {
+                AppState::Reader => app.reader.story.clone(),
+                _ => app.selected_story().cloned(),
+            };
+            if let Some(story) = story {
+                spawn_comments_fetch(tx, story.id, &app.fetch, false);
+                app.open_comments(story);
+            }
+        }
+        Action::ReadPost => {
+            // Read the post's text in full
+            if let Some(story) = app.comments.story.clone() {
+                if story.rich_text().is_some() {
+                    app.open_post(story);
+                }
+            }
+        }
+        Action::Profile => {
+            if let Some(name) = app.selected_author() {
+                spawn_user_fetch(tx, name.clone(), &app.fetch, false);
+                app.open_user(name);
+            }
+        }
+        Action::Search => app.open_search(),
+        Action::EditQuery => app.search.editing = true,
+        Action::Refresh if app.state == AppState::Comments => {
+            // Reload the discussion
+            if let Some(story) = &app.comments.story {
+                spawn_comments_fetch(tx, story.id, &app.fetch, true);
+                app.reload_comments();
+            }
+        }
+        Action::Refresh if app.state == AppState::User => {
+            // Reload the profile and its first page of submissions
+            spawn_user_fetch(tx, app.user.name.clone(), &app.fetch, true);
+            app.reload_user();
+        }
+        Action::Refresh => {
+            app.start_refresh();
+            let feed = app.feed;
+            load_feed(app, tx, feed, true);
+        }
+        Action::ToggleInfo => app.toggle_info(),
+        Action::NextFeed
+        | Action::PreviousFeed
+        | Action::FeedTop
+        | Action::FeedNew
+        | Action::FeedBest
+        | Action::FeedAsk
+        | Action::FeedShow
+        | Action::FeedJobs
+        | Action::ToggleShow => {
+            // Switch feed and load it if needed
+            let feed = match action {
+                Action::NextFeed => app.feed.next(),
+                Action::PreviousFeed => app.feed.previous(),
+                Action::FeedTop => Feed::Top,
+                Action::FeedNew => Feed::New,
+                Action::FeedBest => Feed::Best,
+                Action::FeedAsk => Feed::Ask,
+                Action::FeedShow => Feed::Show,
+                Action::FeedJobs => Feed::Jobs,
+                _ if app.feed == Feed::Show => Feed::Top,
+                _ => Feed::Show,
+            };
+            if app.switch_feed(feed) {
+                load_feed(app, tx, feed, false);
+            }
+        }
+        Action::Parent => app.comments.parent(),
+        Action::FirstReply => app.comments.first_child(),
+        Action::NextSibling => app.comments.next_sibling(),
+        Action::PreviousSibling => app.comments.previous_sibling(),
+        Action::Collapse => app.comments.toggle_collapse(),
+        Action::Bookmark => app.toggle_bookmark(),
+        Action::Saved => app.open_saved(),
+        Action::Filter if app.state == AppState::Saved => {
+            app.start_saved_prompt(SavedPrompt::Filter)
+        }
+        Action::Filter => app.start_filter(),
+        Action::CycleSort => {
+            if let Some(list) = app.story_list_mut() {
+                list.sort = list.sort.next();
+            }
+        }
+        Action::NextMatch | Action::PreviousMatch => {
+            if let Some(list) = app.story_list_mut() {
+                list.select_match(action == Action::NextMatch);
+            }
+            request_more_items(app, tx);
+        }
+        Action::EditTags => app.start_saved_prompt(SavedPrompt::Tags),
+        Action::EditNote => app.start_saved_prompt(SavedPrompt::Note),
+        Action::HideDomain | Action::HideUser => {
+            app.hide_selected(action);
+            request_more_items(app, tx);
+        }
+        Action::RevealHidden => app.toggle_reveal_hidden(),
+        Action::Help => app.overlay = Overlay::Help { scroll: 0 },
+        Action::CommandPalette => app.overlay = Overlay::Palette(Palette::default()),
+    }
+    false
+}
 
-                // Format time
-                let time_str = {
-                    use std::time::{SystemTime, UNIX_EPOCH};
-                    let now = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-                    let diff = now.saturating_sub(story.time);
-                    if diff < 3600 {
-                        format!("{}m", diff / 60)
-                    } else if diff < 86400 {
-                        format!("{}h", diff / 3600)
-                    } else {
-                        format!("{}d", diff / 86400)
-                    }
-                };
+/// Fetches the next page of the list being browsed once the selection nears its end
+fn request_more_items(app: &mut App, tx: &UnboundedSender<AppMessage>) {
+    match app.state {
+        AppState::Stories => {
+            if let Some((feed, generation, start, ids)) = app.request_more() {
+                let task = spawn_page_fetch(tx, feed, generation, start, ids, &app.fetch);
+                app.current_feed_mut().track(task);
+            }
+        }
+        AppState::Search => {
+            if let Some((query, generation, page)) = app.request_more_results() {
+                spawn_search(tx, query, generation, page, &app.fetch);
+            }
+        }
+        AppState::User => {
+            if let Some((name, start, ids)) = app.request_more_submissions() {
+                spawn_submissions_fetch(tx, name, start, ids, &app.fetch);
+            }
+        }
+        _ => {}
+    }
+}
 
-                // Create aligned content with proper spacing
-                let mut content = vec![
-                    // Empty line for spacing
-                    Line::from(""),
-                    // Title line with rank number and Show HN indicator
-                    Line::from(vec![
-                        Span::styled(
-                            format!("{:2}. ", index + 1),
-                            Style::default()
-                                .fg(Color::DarkGray)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(
-                            "Show HN: ",
-                            Style::default()
-                                .fg(Color::Magenta)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(
-                            story
-                                .title
-                                .strip_prefix("Show HN: ")
-                                .unwrap_or(&story.title),
-                            Style::default()
-                                .fg(Color::White)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(
-                            url_display,
-                            Style::default()
-                                .fg(Color::Blue)
-                                .add_modifier(Modifier::ITALIC),
-                        ),
-                    ]),
-                ];
+/// Opens the selected story in the reader: its linked article, or its own text
+/// for posts without a link. Items with neither open their discussion.
+fn read_selected(app: &mut App, tx: &UnboundedSender<AppMessage>) {
+    let Some(story) = app.selected_story().cloned() else {
+        return;
+    };
+    match story.url.clone().filter(|url| !url.is_empty()) {
+        Some(url) => {
+            spawn_article_fetch(tx, url, &app.fetch);
+            app.open_reader(story);
+        }
+        None if story.text.as_deref().is_some_and(|text| !text.is_empty()) => {
+            app.open_post(story);
+        }
+        None => {
+            spawn_comments_fetch(tx, story.id, &app.fetch, false);
+            app.open_comments(story);
+        }
+    }
+}
 
-                // Conditionally add stats line if info is enabled
-                if app.show_info {
-                    content.push(Line::from(vec![
-                        Span::styled("    ", Style::default()), // Indent to align with title
-                        Span::styled(
-                            format!("▲ {:3}", story.score),
-                            Style::default()
-                                .fg(Color::Green)
-                                .add_modifier(Modifier::BOLD),
-                        ),
-                        Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
-                        Span::styled(
-                            format!("👤 {}", story.by),
-                            Style::default().fg(Color::Magenta),
-                        ),
-                        Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
-                        Span::styled(
-                            format!("💬 {:2}", story.descendants.unwrap_or(0)),
-                            Style::default().fg(Color::Cyan),
-                        ),
-                        Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
-                        Span::styled(
-                            format!("🕒 {}", time_str),
-                            Style::default().fg(Color::Yellow),
-                        ),
-                    ]));
-                }
+/// Starts loading the first page of a feed, cancelling the fetches of it that
+/// are still running so an older fetch can never overwrite a newer one.
+///
+/// `force` fetches past the cache, as when the user asks for a refresh.
+fn load_feed(app: &mut App, tx: &UnboundedSender<AppMessage>, feed: Feed, force: bool) {
+    let state = app
+        .feeds
+        .entry(feed)
+        .or_insert_with(|| FeedView::for_feed(feed));
+    let generation = state.start_loading();
+    state.track(spawn_feed_fetch(tx, feed, generation, &app.fetch, force));
+}
 
-                // Empty line for spacing
-                content.push(Line::from(""));
-                ListItem::new(content).style(Style::default())
-            })
-            .collect();
+/// Fetches the stories of a feed in the background, tagging the results with the
+/// fetch's generation
+///
+/// `force` bypasses the fresh cached id list and stories.
+fn spawn_feed_fetch(
+    tx: &UnboundedSender<AppMessage>,
+    feed: Feed,
+    generation: u64,
+    options: &FetchOptions,
+    force: bool,
+) -> JoinHandle<()> {
+    let tx = tx.clone();
+    let options = options.clone();
+    tokio::spawn(async move {
+        let progress_tx = tx.clone();
+        match fetch_stories_with_progress(feed, &options, force, |progress| {
+            let _ = progress_tx.send(AppMessage::Progress(feed, generation, progress));
+        })
+        .await
+        {
+            Ok((ids, stories)) => {
+                let _ = tx.send(AppMessage::StoriesLoaded(feed, generation, ids, stories));
+            }
+            Err(e) => {
+                let _ = tx.send(AppMessage::FeedFailed(feed, generation, e.to_string()));
+            }
+        }
+    })
+}
 
-        // Render the Show HN stories list
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Magenta))
-                    .title(format!(
-                        "🚀 Show HN ({}/{})",
-                        app.show_selected + 1,
-                        app.show_stories.len()
-                    ))
-                    .title_style(
-                        Style::default()
-                            .fg(Color::Magenta)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-            )
-            .highlight_style(
-                Style::default()
-                    .bg(Color::Magenta)
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            )
-            .highlight_symbol("➤ ");
+/// Fetches a further page of a feed in the background
+fn spawn_page_fetch(
+    tx: &UnboundedSender<AppMessage>,
+    feed: Feed,
+    generation: u64,
+    start: usize,
+    ids: Vec<u64>,
+    options: &FetchOptions,
+) -> JoinHandle<()> {
+    let tx = tx.clone();
+    let options = options.clone();
+    tokio::spawn(async move {
+        let stories = fetch_story_page(ids, &options).await;
+        let _ = tx.send(AppMessage::MoreStoriesLoaded(
+            feed, generation, start, stories,
+        ));
+    })
+}
 
-        let mut state = ListState::default();
-        state.select(Some(app.show_selected));
+/// Follows live changes to a feed in the background until aborted.
+///
+/// Uses the event stream while it works and reconnects when it drops. If the stream
+/// cannot be opened at all, polls the feed every [`POLL_INTERVAL`] instead.
+fn spawn_live_updates(
+    tx: &UnboundedSender<AppMessage>,
+    feed: Feed,
+    options: &FetchOptions,
+) -> JoinHandle<()> {
+    let tx = tx.clone();
+    let client = options.client.clone();
+    tokio::spawn(async move {
+        let send = |event| {
+            let _ = tx.send(AppMessage::Live(feed, event));
+        };
 
-        f.render_stateful_widget(list, chunks[1], &mut state);
-    }
+        loop {
+            let connected = AtomicBool::new(false);
+            let _ = client
+                .watch(feed, &|event| {
+                    if event == LiveEvent::Streaming {
+                        connected.store(true, Ordering::Relaxed);
+                    }
+                    send(event);
+                })
+                .await;
+            if !connected.load(Ordering::Relaxed) {
+                break;
+            }
+            tokio::time::sleep(LIVE_RETRY_DELAY).await;
+        }
 
-    // Render footer with Show HN specific instructions
-    let info_status = if app.show_info { "ON" } else { "OFF" };
-    let footer_text = format!(
-        "↑↓ Navigate • Enter Open Link • I Info ({}) • S Stories • R Refresh • Q Quit",
-        info_status
-    );
-    let footer = Paragraph::new(footer_text)
-        .style(
-            Style::default()
-                .fg(Color::White)
-                .bg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Gray))
-                .title("Controls")
-                .title_style(
-                    Style::default()
-                        .fg(Color::Gray)
-                        .add_modifier(Modifier::BOLD),
-                ),
-        );
-    f.render_widget(footer, chunks[2]);
+        send(LiveEvent::Polling);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        // The first tick completes immediately, right after the feed was loaded
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Ok(ids) = client.feed_ids(feed).await {
+                send(LiveEvent::FeedIds(ids));
+            }
+            send(LiveEvent::Tick);
+        }
+    })
 }
 
-// ===== MAIN APPLICATION LOOP =====
+/// Refetches stories of a feed for a live update in the background
+fn spawn_live_items(
+    tx: &UnboundedSender<AppMessage>,
+    feed: Feed,
+    ids: Vec<u64>,
+    options: &FetchOptions,
+) {
+    let tx = tx.clone();
+    let options = options.clone();
+    tokio::spawn(async move {
+        let items = fetch_live_items(ids, &options).await;
+        let _ = tx.send(AppMessage::LiveItems(feed, items));
+    });
+}
 
-/// Runs the main application loop, handling user input and rendering the UI
-async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<()> {
-    // Start loading stories in the background
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+/// Fetches a story's comment tree in the background
+///
+/// `force` bypasses fresh cached comments.
+fn spawn_comments_fetch(
+    tx: &UnboundedSender<AppMessage>,
+    story_id: u64,
+    options: &FetchOptions,
+    force: bool,
+) {
+    let tx = tx.clone();
+    let options = options.clone();
+    tokio::spawn(async move {
+        match fetch_comments(story_id, &options, force).await {
+            Ok((story, poll_options, tree, failed)) => {
+                let _ = tx.send(AppMessage::CommentsLoaded(
+                    Box::new(story),
+                    poll_options,
+                    tree,
+                    failed,
+                ));
+            }
+            Err(e) => {
+                let _ = tx.send(AppMessage::CommentsFailed(story_id, e.to_string()));
+            }
+        }
+    });
+}
 
-    let tx_clone = tx.clone();
+/// Fetches the top comments previewed in the detail pane in the background
+fn spawn_detail_fetch(
+    tx: &UnboundedSender<AppMessage>,
+    story_id: u64,
+    ids: Vec<u64>,
+    options: &FetchOptions,
+) {
+    let tx = tx.clone();
+    let options = options.clone();
     tokio::spawn(async move {
-        match fetch_stories_with_progress(|progress| {
-            let _ = tx_clone.send(AppMessage::Progress(progress));
-        })
-        .await
-        {
-            Ok(stories) => {
-                let _ = tx_clone.send(AppMessage::StoriesLoaded(stories));
+        // A preview just shows fewer comments if some fail to load
+        let comments = fetch_story_page(ids, &options).await.items;
+        let _ = tx.send(AppMessage::DetailLoaded(story_id, comments));
+    });
+}
+
+/// Downloads and extracts a story's article in the background
+fn spawn_article_fetch(tx: &UnboundedSender<AppMessage>, url: String, options: &FetchOptions) {
+    let tx = tx.clone();
+    let options = options.clone();
+    tokio::spawn(async move {
+        match fetch_article(&url, &options).await {
+            Ok(article) => {
+                let _ = tx.send(AppMessage::ArticleLoaded(url, article));
             }
             Err(e) => {
-                let _ = tx_clone.send(AppMessage::Error(e.to_string()));
+                let _ = tx.send(AppMessage::ArticleFailed(url, e.to_string()));
             }
         }
     });
+}
 
-    loop {
-        // Handle background messages
-        while let Ok(msg) = rx.try_recv() {
-            match msg {
-                AppMessage::Progress(progress) => {
-                    app.update_loading_progress(progress);
-                }
-                AppMessage::StoriesLoaded(stories) => {
-                    app.set_stories(stories);
-                }
-                AppMessage::ShowStoriesLoaded(stories) => {
-                    app.set_show_stories(stories);
-                }
-                AppMessage::Error(error) => {
-                    app.set_error(error);
-                }
+/// Fetches a page of search results in the background, tagging them with the
+/// search's generation
+fn spawn_search(
+    tx: &UnboundedSender<AppMessage>,
+    query: SearchQuery,
+    generation: u64,
+    page: usize,
+    options: &FetchOptions,
+) {
+    let tx = tx.clone();
+    let client = options.client.clone();
+    let page_size = options.page_size;
+    tokio::spawn(async move {
+        match client.search(&query, page, page_size).await {
+            Ok(results) => {
+                let _ = tx.send(AppMessage::SearchLoaded(generation, page, results));
+            }
+            Err(e) => {
+                let _ = tx.send(AppMessage::SearchFailed(generation, e.to_string()));
             }
         }
+    });
+}
 
-        // Render the current UI state
-        terminal.draw(|f| ui(f, &mut app))?;
+/// Writes the reading history on a blocking thread, reporting a failure
+fn spawn_history_save(tx: &UnboundedSender<AppMessage>, snapshot: Snapshot) {
+    let tx = tx.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = snapshot.write() {
+            let _ = tx.send(AppMessage::HistoryFailed(format!("{:#}", e)));
+        }
+    });
+}
 
-        // Poll for user input with a timeout
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                // Only handle key press events, ignore key release
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
-                match app.state {
-                    AppState::Loading => match key.code {
-                        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                            return Ok(());
-                        }
-                        _ => {}
-                    },
-                    AppState::Error(_) => {
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                                return Ok(());
-                            }
-                            KeyCode::Char('r') | KeyCode::Char('R') => {
-                                // Restart loading
-                                app.state = AppState::Loading;
-                                app.loading_progress = 0;
-                                let tx_clone = tx.clone();
-                                tokio::spawn(async move {
-                                    match fetch_stories_with_progress(|progress| {
-                                        let _ = tx_clone.send(AppMessage::Progress(progress));
-                                    })
-                                    .await
-                                    {
-                                        Ok(stories) => {
-                                            let _ =
-                                                tx_clone.send(AppMessage::StoriesLoaded(stories));
-                                        }
-                                        Err(e) => {
-                                            let _ = tx_clone.send(AppMessage::Error(e.to_string()));
-                                        }
-                                    }
-                                });
-                            }
-                            _ => {}
-                        }
-                    }
-                    AppState::Stories => {
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                                return Ok(());
-                            }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                app.next();
-                            }
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                app.previous();
-                            }
-                            KeyCode::Enter => {
-                                // Open URL in browser
-                                if let Some(story) = app.selected_story() {
-                                    if let Some(url) = &story.url {
-                                        if !url.is_empty() {
-                                            let _ = open::that(url);
-                                        }
-                                    }
-                                }
-                            }
-                            KeyCode::Char('i') | KeyCode::Char('I') => {
-                                // Toggle info display
-                                app.toggle_info();
-                            }
-                            KeyCode::Char('s') | KeyCode::Char('S') => {
-                                // Switch to Show HN view and load stories if needed
-                                app.switch_to_show_hn();
-                                if app.show_stories.is_empty() {
-                                    let tx_clone = tx.clone();
-                                    tokio::spawn(async move {
-                                        match fetch_show_stories_with_progress(|progress| {
-                                            let _ = tx_clone.send(AppMessage::Progress(progress));
-                                        })
-                                        .await
-                                        {
-                                            Ok(stories) => {
-                                                let _ = tx_clone
-                                                    .send(AppMessage::ShowStoriesLoaded(stories));
-                                            }
-                                            Err(e) => {
-                                                let _ =
-                                                    tx_clone.send(AppMessage::Error(e.to_string()));
-                                            }
-                                        }
-                                    });
-                                }
-                            }
-                            KeyCode::Char('r') | KeyCode::Char('R') => {
-                                // Refresh stories
-                                app.state = AppState::Loading;
-                                app.loading_progress = 0;
-                                app.stories.clear();
-                                let tx_clone = tx.clone();
-                                tokio::spawn(async move {
-                                    match fetch_stories_with_progress(|progress| {
-                                        let _ = tx_clone.send(AppMessage::Progress(progress));
-                                    })
-                                    .await
-                                    {
-                                        Ok(stories) => {
-                                            let _ =
-                                                tx_clone.send(AppMessage::StoriesLoaded(stories));
-                                        }
-                                        Err(e) => {
-                                            let _ = tx_clone.send(AppMessage::Error(e.to_string()));
-                                        }
-                                    }
-                                });
-                            }
-                            _ => {}
-                        }
-                    }
-                    AppState::ShowHN => {
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                                return Ok(());
-                            }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                app.next();
-                            }
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                app.previous();
-                            }
-                            KeyCode::Enter => {
-                                // Open URL in browser
-                                if let Some(story) = app.selected_story() {
-                                    if let Some(url) = &story.url {
-                                        if !url.is_empty() {
-                                            let _ = open::that(url);
-                                        }
-                                    }
-                                }
-                            }
-                            KeyCode::Char('i') | KeyCode::Char('I') => {
-                                // Toggle info display
-                                app.toggle_info();
-                            }
-                            KeyCode::Char('s') | KeyCode::Char('S') => {
-                                // Switch back to regular stories
-                                app.back_to_stories();
-                            }
-                            KeyCode::Char('r') | KeyCode::Char('R') => {
-                                // Refresh Show HN stories
-                                app.show_stories.clear();
-                                let tx_clone = tx.clone();
-                                tokio::spawn(async move {
-                                    match fetch_show_stories_with_progress(|progress| {
-                                        let _ = tx_clone.send(AppMessage::Progress(progress));
-                                    })
-                                    .await
-                                    {
-                                        Ok(stories) => {
-                                            let _ = tx_clone
-                                                .send(AppMessage::ShowStoriesLoaded(stories));
-                                        }
-                                        Err(e) => {
-                                            let _ = tx_clone.send(AppMessage::Error(e.to_string()));
-                                        }
-                                    }
-                                });
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+/// Fetches a user profile in the background
+fn spawn_user_fetch(
+    tx: &UnboundedSender<AppMessage>,
+    name: String,
+    options: &FetchOptions,
+    force: bool,
+) {
+    let tx = tx.clone();
+    let options = options.clone();
+    tokio::spawn(async move {
+        match fetch_user_with_submissions(&name, &options, force).await {
+            Ok((user, submissions)) => {
+                let _ = tx.send(AppMessage::UserLoaded(user, submissions));
+            }
+            Err(e) => {
+                let _ = tx.send(AppMessage::UserFailed(name, e.to_string()));
             }
         }
-    }
+    });
+}
+
+/// Fetches a further page of a user's submissions in the background
+fn spawn_submissions_fetch(
+    tx: &UnboundedSender<AppMessage>,
+    name: String,
+    start: usize,
+    ids: Vec<u64>,
+    options: &FetchOptions,
+) {
+    let tx = tx.clone();
+    let options = options.clone();
+    tokio::spawn(async move {
+        let items = fetch_story_page(ids, &options).await;
+        let _ = tx.send(AppMessage::SubmissionsLoaded(name, start, items));
+    });
 }
 
 /// Messages for background communication
 #[derive(Debug)]
 enum AppMessage {
-    Progress(u16),
-    StoriesLoaded(Vec<Item>),
-    ShowStoriesLoaded(Vec<Item>),
-    Error(String),
+    Progress(Feed, u64, u16),
+    StoriesLoaded(Feed, u64, Vec<u64>, Fetched),
+    FeedFailed(Feed, u64, String),
+    MoreStoriesLoaded(Feed, u64, usize, Fetched),
+    CommentsLoaded(Box<Item>, Vec<Item>, Vec<CommentNode>, usize),
+    CommentsFailed(u64, String),
+    SearchLoaded(u64, usize, SearchPage),
+    SearchFailed(u64, String),
+    UserLoaded(User, Fetched),
+    UserFailed(String, String),
+    SubmissionsLoaded(String, usize, Fetched),
+    DetailLoaded(u64, Vec<Item>),
+    Live(Feed, LiveEvent),
+    LiveItems(Feed, Vec<Item>),
+    ArticleLoaded(String, Article),
+    ArticleFailed(String, String),
+    HistoryFailed(String),
+}
+
+/// Command-line arguments
+#[derive(Parser, Debug)]
+#[command(version, about = "Browse Hacker News in the terminal")]
+struct Args {
+    /// Browse the last cached snapshot without touching the network
+    #[arg(long)]
+    offline: bool,
+
+    /// Base URL of the Hacker News Firebase API
+    #[arg(long, env = "HN_API_URL", default_value = DEFAULT_API_URL)]
+    api_url: String,
+
+    /// Base URL of the Algolia-compatible search API
+    #[arg(long, env = "HN_SEARCH_URL", default_value = DEFAULT_SEARCH_URL)]
+    search_url: String,
+
+    /// Serve everything from a JSON fixture file instead of the network
+    #[arg(long, env = "HN_FIXTURE", value_name = "PATH")]
+    fixture: Option<PathBuf>,
+
+    /// Read settings from this file instead of the XDG config directory
+    #[arg(long, env = "HN_CONFIG", value_name = "PATH")]
+    config: Option<PathBuf>,
 }
 
 /// Main entry point for the Hacker News terminal application
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // ===== CONFIGURATION =====
+    // Invalid settings stop the app here, before the terminal is taken over
+    let config = Config::load(args.config.as_deref())?;
+
+    // ===== DATA SOURCE =====
+    // A fixture replaces the network entirely, so the disk cache is bypassed too
+    let (client, cache): (Arc<dyn HnClient>, Cache) = match &args.fixture {
+        Some(path) => (Arc::new(FixtureClient::load(path)?), Cache::disabled()),
+        None => (
+            Arc::new(HttpClient::new(
+                &args.api_url,
+                &args.search_url,
+                Duration::from_secs(config.network.timeout_secs),
+            )?),
+            Cache::open(args.offline),
+        ),
+    };
+
+    // ===== LOCAL DATA =====
+    // Bookmarks and history made against a fixture are not mixed into the real ones
+    let (bookmarks, history, killfile) = match &args.fixture {
+        Some(_) => (
+            Bookmarks::in_memory(),
+            History::in_memory(),
+            Killfile::in_memory(&config.filters)?,
+        ),
+        None => (
+            Bookmarks::open()?,
+            History::open(),
+            Killfile::open(&config.filters)?,
+        ),
+    };
+
     // ===== TERMINAL SETUP =====
-    let result = std::panic::catch_unwind(|| enable_raw_mode());
+    let result = std::panic::catch_unwind(enable_raw_mode);
 
     if result.is_err() {
         eprintln!("Failed to enable raw mode. Make sure you're running in a proper terminal.");
@@ -1031,10 +5028,17 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // ===== CREATE APP =====
-    let app = App::new();
+    let fetch = FetchOptions {
+        concurrency: config.network.concurrency,
+        page_size: config.feeds.page_size,
+        cache,
+        client: Arc::new(CoalescingClient::new(client)),
+    };
+    let keymap = Keymap::new(&config.keys)?;
+    let mut app = App::new(fetch, config, keymap, bookmarks, history, killfile);
 
     // ===== RUN APPLICATION =====
-    let res = run_app(&mut terminal, app).await;
+    let res = run_app(&mut terminal, &mut app).await;
 
     // ===== TERMINAL CLEANUP =====
     disable_raw_mode()?;
@@ -1050,5 +5054,423 @@ async fn main() -> Result<()> {
         eprintln!("Application error: {:?}", err);
     }
 
+    // Write whatever history changed since the last save
+    if let Some(snapshot) = app.history.take_unsaved() {
+        if let Err(err) = snapshot.write() {
+            eprintln!("{:#}", err);
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Fetch options serving a fixture of five top stories, where story 4 and
+    /// comment 12 are missing
+    fn fixture_options(page_size: usize) -> FetchOptions {
+        let client = FixtureClient::from_json(
+            r#"{
+                "feeds": {"topstories": [1, 2, 3, 4, 5]},
+                "items": [
+                    {"id": 1, "type": "story", "title": "One", "time": 0, "kids": [10, 11, 12]},
+                    {"id": 2, "type": "story", "title": "Two", "time": 0},
+                    {"id": 3, "type": "story", "title": "Three", "time": 0},
+                    {"id": 5, "type": "story", "title": "Five", "time": 0},
+                    {"id": 10, "type": "comment", "time": 0, "kids": [13]},
+                    {"id": 11, "type": "comment", "time": 0, "deleted": true},
+                    {"id": 13, "type": "comment", "time": 0}
+                ],
+                "users": [{"id": "pg", "karma": 100, "submitted": [1, 2]}]
+            }"#,
+        )
+        .unwrap();
+        FetchOptions {
+            concurrency: 2,
+            page_size,
+            cache: Cache::disabled(),
+            client: Arc::new(client),
+        }
+    }
+
+    fn ids(items: &[Item]) -> Vec<u64> {
+        items.iter().map(|item| item.id).collect()
+    }
+
+    /// An app over the fixture that keeps everything in memory
+    fn test_app() -> App {
+        let config = Config::default();
+        let keymap = Keymap::new(&config.keys).unwrap();
+        let killfile = Killfile::in_memory(&config.filters).unwrap();
+        App::new(
+            fixture_options(3),
+            config,
+            keymap,
+            Bookmarks::in_memory(),
+            History::in_memory(),
+            killfile,
+        )
+    }
+
+    /// A story with the given id, score, comment count, age in hours and URL
+    fn story(id: u64, score: u32, comments: u32, hours: u64, url: &str) -> Item {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - hours * 3600;
+        serde_json::from_value(serde_json::json!({
+            "id": id, "type": "story", "title": format!("Story {}", id), "time": time,
+            "score": score, "descendants": comments, "url": url
+        }))
+        .unwrap()
+    }
+
+    /// A feed of `ids` with the first `page_size` of them loaded
+    fn loaded_feed(ids: &[u64], page_size: usize) -> FeedView {
+        let mut view = FeedView::default();
+        let items = ids[..page_size]
+            .iter()
+            .map(|&id| story(id, 10, 1, 1, ""))
+            .collect();
+        view.set_first_page(ids.to_vec(), Fetched { items, failed: 0 }, page_size);
+        view
+    }
+
+    #[test]
+    fn sort_modes_order_rows_by_their_key() {
+        let stories = vec![
+            story(1, 12, 5, 1, "https://www.b.com/x"),
+            story(2, 30, 5, 10, ""),
+            story(3, 30, 50, 3, "https://a.com/y"),
+            story(4, 5, 0, 0, "https://B.com/z"),
+        ];
+        // Ties (score 30, 5 comments, domain b.com) keep their rank order
+        let table = [
+            (SortMode::Rank, [0, 1, 2, 3]),
+            (SortMode::Score, [1, 2, 0, 3]),
+            (SortMode::Comments, [2, 0, 1, 3]),
+            (SortMode::Age, [3, 0, 2, 1]),
+            (SortMode::Velocity, [0, 2, 3, 1]),
+            (SortMode::Domain, [2, 0, 3, 1]),
+        ];
+        for (mode, expected) in table {
+            let mut rows = vec![0, 1, 2, 3];
+            mode.arrange(&stories, &mut rows);
+            assert_eq!(rows, expected, "{:?}", mode);
+        }
+
+        // Rows still point at the stories in rank order, which the list shows
+        let mut view = FeedView {
+            stories,
+            sort: SortMode::Score,
+            ..FeedView::default()
+        };
+        let ranks: Vec<usize> = view.rows().iter().map(|index| index + 1).collect();
+        assert_eq!(ranks, vec![2, 3, 1, 4]);
+        view.filter = "b.com".to_string();
+        assert_eq!(view.rows(), vec![0, 3]);
+
+        let mut mode = SortMode::Rank;
+        for _ in SortMode::ALL {
+            mode = mode.next();
+        }
+        assert_eq!(mode, SortMode::Rank);
+    }
+
+    #[test]
+    fn live_reorder_keeps_the_selection() {
+        let mut view = loaded_feed(&[1, 2, 3, 4, 5, 6], 4);
+        view.selected = 1;
+
+        assert!(view.apply_live_ids(vec![3, 1, 2, 4, 5, 6]).is_empty());
+        assert_eq!(ids(&view.stories), vec![3, 1, 2, 4]);
+        assert_eq!(view.stories[view.selected].id, 2);
+        assert_eq!(view.change(3).unwrap().moved, 2);
+        assert_eq!(view.change(2).unwrap().moved, -1);
+        assert!(view.change(4).is_none());
+
+        // A list being fetched is left alone
+        view.loading = true;
+        assert!(view.apply_live_ids(vec![4, 3, 2, 1]).is_empty());
+        assert_eq!(ids(&view.stories), vec![3, 1, 2, 4]);
+    }
+
+    #[test]
+    fn live_arrivals_are_fetched_only_within_the_loaded_page() {
+        let mut view = loaded_feed(&[1, 2, 3, 4, 5, 6], 4);
+
+        // 7 enters the loaded range and pushes 4 out of it; 8 lands beyond it
+        let missing = view.apply_live_ids(vec![7, 1, 2, 3, 4, 5, 6, 8]);
+        assert_eq!(missing, vec![7]);
+        assert_eq!(view.fetched, 4);
+        assert_eq!(ids(&view.stories), vec![1, 2, 3]);
+        assert!(view.change(7).unwrap().entered);
+        assert!(view.change(8).is_none());
+
+        view.apply_live_items(vec![story(7, 5, 0, 0, ""), story(1, 15, 3, 1, "")]);
+        assert_eq!(ids(&view.stories), vec![7, 1, 2, 3]);
+        assert_eq!(view.stories[view.selected].id, 1);
+        let change = view.change(1).unwrap();
+        assert_eq!((change.score, change.comments), (5, 2));
+    }
+
+    #[test]
+    fn live_removals_drop_stories_and_clamp_the_selection() {
+        let mut view = loaded_feed(&[1, 2, 3, 4, 5, 6], 4);
+        view.selected = 1;
+
+        // 5 moves up into the loaded range in place of the removed 2
+        assert_eq!(view.apply_live_ids(vec![1, 3, 4, 5, 6]), vec![5]);
+        assert_eq!(ids(&view.stories), vec![1, 3, 4]);
+        assert_eq!(view.stories[view.selected].id, 3);
+
+        view.selected = 2;
+        assert!(view.apply_live_ids(vec![1]).is_empty());
+        assert_eq!(ids(&view.stories), vec![1]);
+        assert_eq!((view.fetched, view.selected), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn superseded_feed_fetches_are_discarded() {
+        let mut app = test_app();
+        let options = app.fetch.clone();
+        let (feed_ids, first) = fetch_stories_with_progress(Feed::Top, &options, false, |_| {})
+            .await
+            .unwrap();
+
+        // A refresh supersedes the fetch in flight, whose result is then dropped
+        let superseded = app.current_feed_mut().start_loading();
+        let current = app.current_feed_mut().start_loading();
+        let stale = Fetched {
+            items: vec![story(9, 1, 0, 1, "")],
+            failed: 0,
+        };
+        app.set_stories(Feed::Top, superseded, vec![9], stale);
+        assert!(app.current_feed().stories.is_empty() && app.current_feed().loading);
+        app.set_stories(Feed::Top, current, feed_ids, first);
+        assert_eq!(ids(&app.current_feed().stories), vec![1, 2, 3]);
+
+        // So is a further page requested before another refresh
+        let (feed, generation, start, page) = app.request_more().unwrap();
+        let more = fetch_story_page(page.clone(), &options).await;
+        app.current_feed_mut().start_loading();
+        app.append_stories(feed, generation, start, more);
+        assert_eq!(ids(&app.current_feed().stories), vec![1, 2, 3]);
+        assert_eq!(app.current_feed().fetched, 3);
+
+        // Even with the right generation, only the page the list waits for is added
+        let current = app.current_feed().generation;
+        let more = fetch_story_page(page, &options).await;
+        app.append_stories(feed, current, start, more);
+        assert_eq!(ids(&app.current_feed().stories), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn resubmitted_search_drops_earlier_results() {
+        let mut app = test_app();
+        app.search.input = "rust".to_string();
+        let (_, first) = app.submit_search().unwrap();
+        let (query, second) = app.submit_search().unwrap();
+        assert_eq!(query.text, "rust");
+        assert_ne!(first, second);
+
+        let page = |ids: &[u64]| SearchPage {
+            items: ids.iter().map(|&id| story(id, 1, 0, 1, "")).collect(),
+            pages: 2,
+        };
+        // Page 0 of both searches arrives, but only the latest one is kept
+        app.add_search_results(first, 0, page(&[1, 2]));
+        app.add_search_results(second, 0, page(&[1, 2]));
+        app.add_search_results(second, 0, page(&[1, 2]));
+        assert_eq!(ids(&app.search.results), vec![1, 2]);
+
+        app.set_search_error(first, "timed out".to_string());
+        assert_eq!(app.search.error, None);
+
+        app.search.selected = 1;
+        let (_, generation, next) = app.request_more_results().unwrap();
+        assert_eq!((generation, next), (second, 1));
+        app.add_search_results(generation, next, page(&[3]));
+        assert_eq!(ids(&app.search.results), vec![1, 2, 3]);
+
+        // A query that does not parse still invalidates the search in flight
+        app.search.input = "since:7x".to_string();
+        assert!(app.submit_search().is_none());
+        app.add_search_results(second, 2, page(&[4]));
+        assert!(app.search.results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn offline_serves_only_the_cache() {
+        let options = fixture_options(30);
+        let online = Cache::temporary(false);
+        let client = options.client.as_ref();
+        fetch_feed_ids(client, &online, Feed::Top, false)
+            .await
+            .unwrap();
+        fetch_item(client, &online, 1, false).await.unwrap();
+
+        // No network is used offline: an empty client could not answer
+        let mut offline = online.clone();
+        offline.offline = true;
+        let none = FixtureClient::default();
+        let ids = fetch_feed_ids(&none, &offline, Feed::Top, true)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(fetch_item(&none, &offline, 1, true).await.unwrap().id, 1);
+        let error = fetch_item(&none, &offline, 2, false).await.unwrap_err();
+        assert_eq!(error.to_string(), "item 2 is not cached");
+        assert!(fetch_feed_ids(&none, &offline, Feed::New, false)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn force_skips_fresh_entries_but_falls_back_to_them() {
+        let options = fixture_options(30);
+        let cache = Cache::temporary(false);
+        let mut old = options.client.item(1).await.unwrap();
+        old.title = Some("Old".to_string());
+        cache.store_item(&old).await;
+
+        let client = options.client.as_ref();
+        let title = |item: Item| item.title.unwrap();
+        assert_eq!(
+            title(fetch_item(client, &cache, 1, false).await.unwrap()),
+            "Old"
+        );
+        assert_eq!(
+            title(fetch_item(client, &cache, 1, true).await.unwrap()),
+            "One"
+        );
+
+        // A failed forced fetch still shows the cached copy
+        old.title = Some("Old".to_string());
+        cache.store_item(&old).await;
+        let none = FixtureClient::default();
+        assert_eq!(
+            title(fetch_item(&none, &cache, 1, true).await.unwrap()),
+            "Old"
+        );
+    }
+
+    #[tokio::test]
+    async fn forced_profile_fetch_skips_the_fresh_profile() {
+        let options = fixture_options(30);
+        let cache = Cache::temporary(false);
+        let mut old = options.client.user("pg").await.unwrap();
+        old.karma = 1;
+        cache.store_user(&old).await;
+
+        let client = options.client.as_ref();
+        let karma = |user: User| user.karma;
+        assert_eq!(
+            karma(fetch_user(client, &cache, "pg", false).await.unwrap()),
+            1
+        );
+        assert_eq!(
+            karma(fetch_user(client, &cache, "pg", true).await.unwrap()),
+            100
+        );
+
+        // A failed forced fetch still shows the cached profile
+        cache.store_user(&old).await;
+        let none = FixtureClient::default();
+        assert_eq!(
+            karma(fetch_user(&none, &cache, "pg", true).await.unwrap()),
+            1
+        );
+        assert!(fetch_user(&none, &cache, "dang", true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn first_page_reports_progress() {
+        let options = fixture_options(3);
+        let progress = Mutex::new(Vec::new());
+        let (feed_ids, page) = fetch_stories_with_progress(Feed::Top, &options, false, |p| {
+            progress.lock().unwrap().push(p)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(feed_ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(ids(&page.items), vec![1, 2, 3]);
+        assert_eq!(page.failed, 0);
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.first(), Some(&10));
+        assert_eq!(progress.last(), Some(&100));
+        assert!(progress.windows(2).all(|pair| pair[0] <= pair[1]));
+        // One update per item between the id list and the end
+        assert_eq!(progress.len(), 2 + 3 + 1);
+    }
+
+    #[tokio::test]
+    async fn later_pages_continue_the_feed() {
+        let options = fixture_options(3);
+        let (feed_ids, first) = fetch_stories_with_progress(Feed::Top, &options, false, |_| {})
+            .await
+            .unwrap();
+
+        let mut view = FeedView::for_feed(Feed::Top);
+        view.start_loading();
+        view.set_first_page(feed_ids, first, options.page_size);
+        let (start, rest) = view.reserve_page(options.page_size).unwrap();
+        assert_eq!((start, rest.clone()), (3, vec![4, 5]));
+
+        view.append_page(
+            start,
+            fetch_story_page(rest, &options).await,
+            options.page_size,
+        );
+        assert_eq!(ids(&view.stories), vec![1, 2, 3, 5]);
+        assert_eq!(view.failed, 1);
+        assert!(!view.has_more());
+        assert!(view.reserve_page(options.page_size).is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_items_are_counted_and_order_is_kept() {
+        let options = fixture_options(30);
+        let page = fetch_items(&options, &[5, 4, 99, 2, 1], false, |_| {}).await;
+        assert_eq!(ids(&page.items), vec![5, 2, 1]);
+        assert_eq!(page.failed, 2);
+
+        let (feed_ids, page) = fetch_stories_with_progress(Feed::Top, &options, false, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(feed_ids.len(), 5);
+        assert_eq!(ids(&page.items), vec![1, 2, 3, 5]);
+        assert_eq!(page.failed, 1);
+    }
+
+    #[tokio::test]
+    async fn feed_without_ids_loads_empty() {
+        let options = fixture_options(30);
+        let (feed_ids, page) = fetch_stories_with_progress(Feed::Jobs, &options, false, |_| {})
+            .await
+            .unwrap();
+        assert!(feed_ids.is_empty() && page.items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn comment_tree_keeps_structure_and_counts_failures() {
+        let options = fixture_options(30);
+        let (story, poll_options, tree, failed) = fetch_comments(1, &options, false).await.unwrap();
+
+        assert_eq!(story.id, 1);
+        assert!(poll_options.is_empty());
+        // 11 is deleted without replies and 12 is missing
+        assert_eq!(failed, 1);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].item.id, 10);
+        assert_eq!(tree[0].replies.len(), 1);
+        assert_eq!(tree[0].replies[0].item.id, 13);
+        assert!(fetch_comments(4, &options, false).await.is_err());
+    }
+}
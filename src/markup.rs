@@ -0,0 +1,760 @@
+//! Styled text blocks and their layout into terminal lines.
+//!
+//! HTML, either HN's small subset in item text or a whole article, is converted
+//! into a [`RichText`] of [`Block`]s, and [`layout`] word-wraps it to the width of
+//! a pane as ratatui [`Line`]s.
+
+use ego_tree::NodeRef;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Inline styling of a run of text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Emphasis {
+    pub bold: bool,
+    pub italic: bool,
+    /// Inline code
+    pub code: bool,
+    /// Text of a link
+    pub link: bool,
+    /// A `[n]` reference after a link
+    pub reference: bool,
+}
+
+impl Emphasis {
+    /// Terminal style for text with this emphasis
+    fn style(self, base: Style) -> Style {
+        let mut style = base;
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if self.code {
+            style = style.fg(Color::LightGreen);
+        }
+        if self.link {
+            style = style
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::UNDERLINED);
+        }
+        if self.reference {
+            style = style.fg(Color::DarkGray);
+        }
+        style
+    }
+}
+
+/// A piece of text with uniform emphasis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub text: String,
+    pub emphasis: Emphasis,
+}
+
+/// A block-level element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Heading of level 1 to 6
+    Heading(u8, Vec<Run>),
+    Paragraph(Vec<Run>),
+    /// Quoted paragraph
+    Quote(Vec<Run>),
+    /// List item at a nesting depth, with its marker ("•", "3.") or an empty
+    /// marker for a continuation paragraph
+    Item {
+        depth: usize,
+        marker: String,
+        runs: Vec<Run>,
+    },
+    /// Preformatted code, kept verbatim
+    Code(String),
+    /// Horizontal rule
+    Rule,
+}
+
+/// Converted text: blocks plus the link targets they reference
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RichText {
+    pub blocks: Vec<Block>,
+    pub links: Vec<String>,
+}
+
+/// Converts an HN item's HTML text (`<p>`, `<i>`, `<a>`, `<pre><code>` and
+/// entities, with `>` marking quoted paragraphs)
+pub fn from_hn_html(html: &str) -> RichText {
+    let fragment = Html::parse_fragment(html);
+    from_element(fragment.root_element(), None, |_| false)
+}
+
+/// Converts an element's subtree, leaving out elements for which `skip` is true
+/// and resolving relative links against `base`
+pub fn from_element(root: ElementRef, base: Option<Url>, skip: fn(ElementRef) -> bool) -> RichText {
+    let mut walker = Walker {
+        builder: Builder::default(),
+        skip,
+        base,
+        lists: Vec::new(),
+        item: None,
+        quote: 0,
+    };
+    walker.children(*root);
+    walker.builder.finish()
+}
+
+/// Flattens rich text into plain paragraphs separated by blank lines
+pub fn to_plain(text: &RichText) -> String {
+    let paragraphs: Vec<String> = text
+        .blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading(_, runs)
+            | Block::Paragraph(runs)
+            | Block::Quote(runs)
+            | Block::Item { runs, .. } => runs
+                .iter()
+                .filter(|run| !run.emphasis.reference)
+                .map(|run| run.text.as_str())
+                .collect(),
+            Block::Code(code) => code.clone(),
+            Block::Rule => String::new(),
+        })
+        .collect();
+    paragraphs.join("\n\n")
+}
+
+/// Accumulates inline text into blocks while walking a document
+#[derive(Debug, Default)]
+struct Builder {
+    text: RichText,
+    /// Runs of the block being assembled
+    runs: Vec<Run>,
+    /// Emphasis applied to text added next
+    emphasis: Emphasis,
+}
+
+impl Builder {
+    /// Adds inline text, collapsing whitespace as HTML does
+    fn text(&mut self, text: &str) {
+        let mut collapsed = String::with_capacity(text.len());
+        let mut space = self
+            .runs
+            .last()
+            .is_none_or(|run| run.text.ends_with([' ', '\n']));
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !space {
+                    collapsed.push(' ');
+                    space = true;
+                }
+            } else {
+                collapsed.push(c);
+                space = false;
+            }
+        }
+        self.push_run(collapsed);
+    }
+
+    /// Forces a line break inside the current block
+    fn line_break(&mut self) {
+        self.push_run("\n".to_string());
+    }
+
+    /// Registers a link target, returning its index in [`RichText::links`]
+    fn link(&mut self, url: String) -> usize {
+        match self.text.links.iter().position(|link| *link == url) {
+            Some(index) => index,
+            None => {
+                self.text.links.push(url);
+                self.text.links.len() - 1
+            }
+        }
+    }
+
+    /// Adds the `[n]` reference that follows a link's text
+    fn link_reference(&mut self, index: usize) {
+        let emphasis = std::mem::replace(
+            &mut self.emphasis,
+            Emphasis {
+                reference: true,
+                ..Emphasis::default()
+            },
+        );
+        self.push_run(format!("[{}]", index + 1));
+        self.emphasis = emphasis;
+    }
+
+    /// Ends the current block, wrapping its runs with `kind` unless it is empty
+    fn flush(&mut self, kind: impl FnOnce(Vec<Run>) -> Block) {
+        let mut runs = std::mem::take(&mut self.runs);
+        while let Some(last) = runs.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            if trimmed > 0 {
+                last.text.truncate(trimmed);
+                break;
+            }
+            runs.pop();
+        }
+        if !runs.is_empty() {
+            self.text.blocks.push(kind(runs));
+        }
+    }
+
+    /// Adds a whole block, ending any pending paragraph first
+    fn push(&mut self, block: Block) {
+        self.flush(Block::Paragraph);
+        self.text.blocks.push(block);
+    }
+
+    /// Finishes the text, ending any pending paragraph
+    fn finish(mut self) -> RichText {
+        self.flush(Block::Paragraph);
+        self.text
+    }
+
+    /// Appends text with the current emphasis, merging it into the last run if possible
+    fn push_run(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some(last) if last.emphasis == self.emphasis => last.text.push_str(&text),
+            _ => self.runs.push(Run {
+                text,
+                emphasis: self.emphasis,
+            }),
+        }
+    }
+}
+
+/// Converts an HTML element tree into rich text
+struct Walker {
+    builder: Builder,
+    /// Elements whose subtrees are left out
+    skip: fn(ElementRef) -> bool,
+    /// Base URL that relative links are resolved against
+    base: Option<Url>,
+    /// Enclosing lists: `None` for bullets, `Some(n)` for the next number
+    lists: Vec<Option<usize>>,
+    /// Marker of the list item whose first paragraph is pending
+    item: Option<String>,
+    /// Depth of enclosing block quotes
+    quote: usize,
+}
+
+impl Walker {
+    /// Converts every child of a node
+    fn children(&mut self, node: NodeRef<Node>) {
+        for child in node.children() {
+            self.node(child);
+        }
+    }
+
+    /// Converts a node and its subtree
+    fn node(&mut self, node: NodeRef<Node>) {
+        match node.value() {
+            Node::Text(text) => self.builder.text(text),
+            Node::Element(_) => {
+                if let Some(element) = ElementRef::wrap(node) {
+                    if !(self.skip)(element) {
+                        self.element(element);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ends the pending block as a paragraph, quote or list item
+    fn flush(&mut self) {
+        if self.quote > 0 {
+            self.builder.flush(Block::Quote);
+        } else if !self.lists.is_empty() {
+            let depth = self.lists.len() - 1;
+            let marker = self.item.take().unwrap_or_default();
+            self.builder.flush(|runs| Block::Item {
+                depth,
+                marker,
+                runs,
+            });
+        } else {
+            // HN marks quotes with a leading ">" rather than a blockquote
+            self.builder
+                .flush(|mut runs| match runs[0].text.strip_prefix('>') {
+                    Some(rest) => {
+                        runs[0].text = rest.trim_start().to_string();
+                        Block::Quote(runs)
+                    }
+                    None => Block::Paragraph(runs),
+                });
+        }
+    }
+
+    /// Converts an element and its subtree
+    fn element(&mut self, element: ElementRef) {
+        let tag = element.value().name();
+        let emphasis = self.builder.emphasis;
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                let level = tag[1..].parse().unwrap_or(1);
+                self.children(*element);
+                self.builder.flush(|runs| Block::Heading(level, runs));
+            }
+            "pre" => {
+                self.flush();
+                let code: String = element.text().collect();
+                self.builder.push(Block::Code(code));
+            }
+            "br" => self.builder.line_break(),
+            "hr" => {
+                self.flush();
+                self.builder.push(Block::Rule);
+            }
+            "em" | "i" | "cite" => {
+                self.builder.emphasis.italic = true;
+                self.children(*element);
+            }
+            "strong" | "b" => {
+                self.builder.emphasis.bold = true;
+                self.children(*element);
+            }
+            "code" | "kbd" | "samp" | "tt" => {
+                self.builder.emphasis.code = true;
+                self.children(*element);
+            }
+            "a" => {
+                let target = element
+                    .value()
+                    .attr("href")
+                    .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"))
+                    .and_then(|href| match &self.base {
+                        Some(base) => base.join(href).ok().map(String::from),
+                        None => Some(href.to_string()),
+                    });
+                match target {
+                    Some(url) => {
+                        // A link showing its own full URL needs no reference
+                        let text: String = element.text().collect();
+                        let bare = text.trim() == url;
+                        self.builder.emphasis.link = true;
+                        self.children(*element);
+                        self.builder.emphasis = emphasis;
+                        if !bare {
+                            let index = self.builder.link(url);
+                            self.builder.link_reference(index);
+                        }
+                    }
+                    None => self.children(*element),
+                }
+            }
+            "blockquote" => {
+                self.flush();
+                self.quote += 1;
+                self.children(*element);
+                self.flush();
+                self.quote -= 1;
+            }
+            "ul" | "ol" => {
+                self.flush();
+                let start = element
+                    .value()
+                    .attr("start")
+                    .and_then(|start| start.parse().ok())
+                    .unwrap_or(1);
+                self.lists.push((tag == "ol").then_some(start));
+                self.children(*element);
+                self.flush();
+                self.lists.pop();
+            }
+            "li" => {
+                self.flush();
+                self.item = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                });
+                self.children(*element);
+                self.flush();
+            }
+            "p" | "div" | "section" | "article" | "main" | "figure" | "figcaption" | "table"
+            | "tr" | "dl" | "dt" | "dd" | "center" | "details" | "summary" => {
+                self.flush();
+                self.children(*element);
+                self.flush();
+            }
+            "td" | "th" => {
+                self.children(*element);
+                self.builder.text("  ");
+            }
+            _ => self.children(*element),
+        }
+        self.builder.emphasis = emphasis;
+    }
+}
+
+/// Lays out rich text for a pane `width` columns wide, ending with a numbered
+/// list of the links it references
+pub fn layout(text: &RichText, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(10);
+    let mut lines = Vec::new();
+
+    let mut previous: Option<&Block> = None;
+    for block in &text.blocks {
+        // Items of the same list sit on consecutive lines
+        let list_continues =
+            matches!(previous, Some(Block::Item { .. })) && matches!(block, Block::Item { .. });
+        if !lines.is_empty() && !list_continues {
+            lines.push(Line::from(""));
+        }
+        previous = Some(block);
+        match block {
+            Block::Heading(level, runs) => {
+                let style = if *level <= 1 {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                } else {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                };
+                lines.extend(wrap(runs, width, style, Vec::new(), Vec::new()));
+            }
+            Block::Paragraph(runs) => {
+                lines.extend(wrap(runs, width, Style::default(), Vec::new(), Vec::new()));
+            }
+            Block::Quote(runs) => {
+                let bar = || vec![Span::styled("▌ ", Style::default().fg(Color::DarkGray))];
+                let style = Style::default()
+                    .fg(Color::Gray)
+                    .add_modifier(Modifier::ITALIC);
+                lines.extend(wrap(runs, width, style, bar(), bar()));
+            }
+            Block::Item {
+                depth,
+                marker,
+                runs,
+            } => {
+                let indent = "  ".repeat(*depth);
+                let marker_width = marker.width().max(1) + 1;
+                let padding = " ".repeat(marker_width - marker.width());
+                let first = vec![Span::styled(
+                    format!("{}{}{}", indent, marker, padding),
+                    Style::default().fg(Color::Yellow),
+                )];
+                let rest = vec![Span::raw(format!("{}{}", indent, " ".repeat(marker_width)))];
+                lines.extend(wrap(runs, width, Style::default(), first, rest));
+            }
+            Block::Code(code) => {
+                let style = Style::default().fg(Color::LightGreen);
+                let gutter = Style::default().fg(Color::DarkGray);
+                for source_line in code.trim_end_matches('\n').lines() {
+                    // Lines wider than the pane continue on the next line
+                    let source_line = source_line.replace('\t', "    ");
+                    let mut remaining = source_line.as_str();
+                    loop {
+                        let (chunk, tail) =
+                            split_at_width(remaining, width.saturating_sub(2), true);
+                        lines.push(Line::from(vec![
+                            Span::styled("│ ", gutter),
+                            Span::styled(chunk.to_string(), style),
+                        ]));
+                        remaining = tail;
+                        if remaining.is_empty() {
+                            break;
+                        }
+                    }
+                }
+            }
+            Block::Rule => {
+                lines.push(Line::from(Span::styled(
+                    "─".repeat(width),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+        }
+    }
+
+    if !text.links.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Links",
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (index, url) in text.links.iter().enumerate() {
+            let reference = format!("[{}] ", index + 1);
+            let runs = [Run {
+                text: url.clone(),
+                emphasis: Emphasis::default(),
+            }];
+            let first = vec![Span::styled(
+                reference.clone(),
+                Style::default().fg(Color::DarkGray),
+            )];
+            let rest = vec![Span::raw(" ".repeat(reference.width()))];
+            let style = Style::default().fg(Color::Blue);
+            lines.extend(wrap(&runs, width, style, first, rest));
+        }
+    }
+
+    lines
+}
+
+/// A word made of styled pieces, or a break between words
+enum Atom {
+    Word(Vec<(String, Style)>),
+    Space,
+    Break,
+}
+
+/// Word-wraps runs to `width`, starting the first line with `first` and later
+/// lines with `rest`
+fn wrap(
+    runs: &[Run],
+    width: usize,
+    base: Style,
+    first: Vec<Span<'static>>,
+    rest: Vec<Span<'static>>,
+) -> Vec<Line<'static>> {
+    // Split the runs into words, keeping the style of every piece
+    let mut atoms = Vec::new();
+    let mut word: Vec<(String, Style)> = Vec::new();
+    for run in runs {
+        let style = run.emphasis.style(base);
+        for c in run.text.chars() {
+            if c == '\n' || c.is_whitespace() {
+                if !word.is_empty() {
+                    atoms.push(Atom::Word(std::mem::take(&mut word)));
+                }
+                atoms.push(if c == '\n' { Atom::Break } else { Atom::Space });
+                continue;
+            }
+            match word.last_mut() {
+                Some((text, piece_style)) if *piece_style == style => text.push(c),
+                _ => word.push((c.to_string(), style)),
+            }
+        }
+    }
+    if !word.is_empty() {
+        atoms.push(Atom::Word(word));
+    }
+
+    let prefix_width = |prefix: &[Span]| prefix.iter().map(|span| span.width()).sum::<usize>();
+    let rest_width = width.saturating_sub(prefix_width(&rest)).max(1);
+    let mut lines = Vec::new();
+    let mut spans = first.clone();
+    let mut available = width.saturating_sub(prefix_width(&first)).max(1);
+    let mut used = 0;
+    let mut pending_space = false;
+
+    for atom in atoms {
+        match atom {
+            Atom::Space => pending_space = used > 0,
+            Atom::Break => {
+                lines.push(Line::from(std::mem::replace(&mut spans, rest.clone())));
+                available = rest_width;
+                used = 0;
+                pending_space = false;
+            }
+            Atom::Word(pieces) => {
+                let word_width: usize = pieces.iter().map(|(text, _)| text.width()).sum();
+                let space = usize::from(pending_space);
+                if used > 0 && used + space + word_width > available {
+                    lines.push(Line::from(std::mem::replace(&mut spans, rest.clone())));
+                    available = rest_width;
+                    used = 0;
+                } else if pending_space {
+                    spans.push(Span::styled(" ", base));
+                    used += 1;
+                }
+                pending_space = false;
+
+                for (text, style) in pieces {
+                    // Words longer than a whole line are split between lines
+                    let mut text = text.as_str();
+                    while used + text.width() > available {
+                        let (head, tail) = split_at_width(text, available - used, used == 0);
+                        if !head.is_empty() {
+                            spans.push(Span::styled(head.to_string(), style));
+                        }
+                        lines.push(Line::from(std::mem::replace(&mut spans, rest.clone())));
+                        available = rest_width;
+                        used = 0;
+                        text = tail;
+                    }
+                    used += text.width();
+                    spans.push(Span::styled(text.to_string(), style));
+                }
+            }
+        }
+    }
+    lines.push(Line::from(spans));
+    lines
+}
+
+/// Splits `text` after the characters that fit in `width` terminal columns.
+///
+/// With `at_least_one`, a first character wider than `width` is still split off,
+/// so that splitting repeatedly always makes progress.
+fn split_at_width(text: &str, width: usize, at_least_one: bool) -> (&str, &str) {
+    let mut used = 0;
+    for (index, c) in text.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            let index = if index == 0 && at_least_one {
+                c.len_utf8()
+            } else {
+                index
+            };
+            return text.split_at(index);
+        }
+    }
+    (text, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(text: &str, emphasis: Emphasis) -> Run {
+        Run {
+            text: text.to_string(),
+            emphasis,
+        }
+    }
+
+    fn plain(text: &str) -> Run {
+        run(text, Emphasis::default())
+    }
+
+    /// The text of laid out lines, without styles
+    fn lines(text: &RichText, width: usize) -> Vec<String> {
+        layout(text, width)
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn paragraph(text: &str) -> RichText {
+        RichText {
+            blocks: vec![Block::Paragraph(vec![plain(text)])],
+            links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn hn_paragraphs_italics_and_links() {
+        let text = from_hn_html(
+            "First line<p>Second <i>word</i> and <a href=\"https://example.com/a\" \
+             rel=\"nofollow\">a link</a><p><a href=\"https://example.com/b\">https://example.com/b</a>",
+        );
+        let italic = Emphasis {
+            italic: true,
+            ..Emphasis::default()
+        };
+        let link = Emphasis {
+            link: true,
+            ..Emphasis::default()
+        };
+        let reference = Emphasis {
+            reference: true,
+            ..Emphasis::default()
+        };
+        assert_eq!(
+            text.blocks,
+            vec![
+                Block::Paragraph(vec![plain("First line")]),
+                Block::Paragraph(vec![
+                    plain("Second "),
+                    run("word", italic),
+                    plain(" and "),
+                    run("a link", link),
+                    run("[1]", reference),
+                ]),
+                // A link showing its own URL gets no reference
+                Block::Paragraph(vec![run("https://example.com/b", link)]),
+            ]
+        );
+        assert_eq!(text.links, vec!["https://example.com/a"]);
+    }
+
+    #[test]
+    fn hn_quotes_code_and_entities() {
+        let text = from_hn_html(
+            "&gt; quoted &quot;text&quot;<p>I &#x27;disagree&#x27; &lt;strongly&gt; &amp; &#x2F;\
+             <p><pre><code>  fn main() {\n      run();\n  }\n</code></pre>",
+        );
+        assert_eq!(
+            text.blocks,
+            vec![
+                Block::Quote(vec![plain("quoted \"text\"")]),
+                Block::Paragraph(vec![plain("I 'disagree' <strongly> & /")]),
+                Block::Code("  fn main() {\n      run();\n  }\n".to_string()),
+            ]
+        );
+        assert!(text.links.is_empty());
+    }
+
+    #[test]
+    fn wraps_at_the_width_boundary() {
+        // Exactly ten columns fit on a line of ten
+        assert_eq!(
+            lines(&paragraph("aaaa bbbbb cccc"), 10),
+            vec!["aaaa bbbbb", "cccc"]
+        );
+        assert_eq!(
+            lines(&paragraph("aaaa bbbbbb cccc"), 10),
+            vec!["aaaa", "bbbbbb", "cccc"]
+        );
+        // Words longer than a line are split
+        assert_eq!(
+            lines(&paragraph("abcdefghijklmnop"), 10),
+            vec!["abcdefghij", "klmnop"]
+        );
+    }
+
+    #[test]
+    fn wide_characters_take_two_columns() {
+        assert_eq!(
+            lines(&paragraph("日本語 日本語"), 10),
+            vec!["日本語", "日本語"]
+        );
+        assert_eq!(
+            lines(&paragraph("日本語の文章です"), 10),
+            vec!["日本語の文", "章です"]
+        );
+        // A wide character that would straddle the edge moves to the next line
+        assert_eq!(
+            lines(&paragraph("a日本語の文章です"), 10),
+            vec!["a日本語の", "文章です"]
+        );
+        let code = RichText {
+            blocks: vec![Block::Code("コードコード".to_string())],
+            links: Vec::new(),
+        };
+        assert_eq!(lines(&code, 10), vec!["│ コードコ", "│ ード"]);
+    }
+
+    #[test]
+    fn layout_lists_link_targets() {
+        let text = from_hn_html("See <a href=\"https://example.com/a\">this</a>");
+        assert_eq!(
+            lines(&text, 40),
+            vec!["See this[1]", "", "Links", "[1] https://example.com/a"]
+        );
+    }
+}
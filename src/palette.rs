@@ -0,0 +1,180 @@
+//! Command palette.
+//!
+//! Lists every action, those of the current screen first, and narrows them down
+//! with fuzzy matching as the user types, so every feature can be found without
+//! knowing its key. Actions of other screens are listed with the screen they run
+//! on.
+
+use crate::keymap::{Action, Keymap, Mode};
+
+/// Bonus for a match at the start of the text or of a word
+const WORD_START_BONUS: i64 = 10;
+
+/// Bonus for a match right after the previous one
+const CONSECUTIVE_BONUS: i64 = 5;
+
+/// An action listed in the palette
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub action: Action,
+    /// Keys bound to the action on the current screen
+    pub keys: Vec<String>,
+    /// Screen the action runs on, if it is not available on the current one
+    pub elsewhere: Option<Mode>,
+}
+
+/// Every action of the keymap: those of `mode` with their keys, then the ones
+/// only found on other screens
+pub fn entries(keymap: &Keymap, mode: Mode) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = keymap
+        .bindings(mode)
+        .into_iter()
+        .map(|(action, keys)| Entry {
+            action,
+            keys,
+            elsewhere: None,
+        })
+        .collect();
+    for other in Mode::ALL {
+        for (action, _) in keymap.bindings(other) {
+            if entries.iter().all(|entry| entry.action != action) {
+                entries.push(Entry {
+                    action,
+                    keys: Vec::new(),
+                    elsewhere: Some(other),
+                });
+            }
+        }
+    }
+    entries
+}
+
+/// State of the open command palette
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Palette {
+    /// Text typed so far
+    pub input: String,
+    /// Index of the highlighted entry among the matches
+    pub selected: usize,
+}
+
+impl Palette {
+    /// Indexes of the entries matching the input, those of the current screen
+    /// first and best match first among them
+    pub fn matches(&self, entries: &[Entry]) -> Vec<usize> {
+        let mut scored: Vec<(bool, i64, usize)> = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let description = fuzzy_score(&self.input, entry.action.description());
+                let name = fuzzy_score(&self.input, entry.action.name());
+                Some((entry.elsewhere.is_some(), description.max(name)?, index))
+            })
+            .collect();
+        // Stable sort keeps the keymap order among equally good matches
+        scored.sort_by_key(|&(elsewhere, score, _)| (elsewhere, std::cmp::Reverse(score)));
+        scored.into_iter().map(|(_, _, index)| index).collect()
+    }
+
+    /// Moves the highlight down, wrapping around `count` matches
+    pub fn next(&mut self, count: usize) {
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    /// Moves the highlight up, wrapping around `count` matches
+    pub fn previous(&mut self, count: usize) {
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+}
+
+/// Scores how well `query` matches `text` as a case-insensitive subsequence.
+///
+/// Returns `None` if some character of the query does not occur in order. Matches
+/// at word starts and runs of consecutive characters score higher, gaps lower.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&c| c == wanted)?;
+        let word_start = found == 0 || !text[found - 1].is_alphanumeric();
+        if word_start {
+            score += WORD_START_BONUS;
+        }
+        match previous {
+            Some(previous) if found == previous + 1 => score += CONSECUTIVE_BONUS,
+            Some(previous) => score -= (found - previous - 1) as i64,
+            None => score -= found as i64,
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeySettings;
+
+    fn stories_entries() -> Vec<Entry> {
+        entries(
+            &Keymap::new(&KeySettings::default()).unwrap(),
+            Mode::Stories,
+        )
+    }
+
+    #[test]
+    fn lists_every_action_once() {
+        let entries = stories_entries();
+        assert_eq!(entries.len(), Action::ALL.len());
+        for action in Action::ALL {
+            assert_eq!(entries.iter().filter(|e| e.action == action).count(), 1);
+        }
+    }
+
+    #[test]
+    fn current_screen_comes_first() {
+        let entries = stories_entries();
+        let first_elsewhere = entries.iter().position(|e| e.elsewhere.is_some()).unwrap();
+        assert!(entries[first_elsewhere..]
+            .iter()
+            .all(|e| e.elsewhere.is_some()));
+
+        let collapse = entries
+            .iter()
+            .find(|e| e.action == Action::Collapse)
+            .unwrap();
+        assert_eq!(collapse.elsewhere, Some(Mode::Comments));
+        assert!(collapse.keys.is_empty());
+
+        // A better match on another screen still ranks below the current screen
+        let palette = Palette {
+            input: "re".to_string(),
+            selected: 0,
+        };
+        let matches = palette.matches(&entries);
+        let elsewhere: Vec<bool> = matches
+            .iter()
+            .map(|&i| entries[i].elsewhere.is_some())
+            .collect();
+        assert!(elsewhere.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(matches
+            .iter()
+            .any(|&i| entries[i].action == Action::FirstReply));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts() {
+        assert!(fuzzy_score("rf", "Refresh").is_some());
+        assert!(fuzzy_score("xyz", "Refresh").is_none());
+        assert!(fuzzy_score("ref", "Refresh") > fuzzy_score("efr", "Refresh"));
+        assert!(fuzzy_score("ns", "Next sibling") > fuzzy_score("ns", "Open in browser"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Readability-style article extraction.
+//!
+//! Scores the elements of a downloaded page by the paragraphs they contain, picks
+//! the one most likely to hold the article, and converts it to [`RichText`]
+//! without navigation, sidebars, comments and other boilerplate.
+
+use std::collections::HashMap;
+
+use ego_tree::NodeId;
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::markup::{self, RichText};
+
+/// Elements that never contain article content
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "nav", "header", "footer", "aside", "form",
+    "iframe", "svg", "canvas", "button", "select", "input", "textarea", "img", "picture", "video",
+    "audio", "object", "embed",
+];
+
+/// Class and id fragments that mark boilerplate
+const UNLIKELY: &[&str] = &[
+    "comment",
+    "sidebar",
+    "footer",
+    "footnote",
+    "nav",
+    "menu",
+    "share",
+    "social",
+    "related",
+    "promo",
+    "advert",
+    "banner",
+    "cookie",
+    "subscribe",
+    "newsletter",
+    "popup",
+    "modal",
+    "breadcrumb",
+    "masthead",
+    "sponsor",
+    "widget",
+    "hidden",
+];
+
+/// Class and id fragments that mark article content
+const LIKELY: &[&str] = &[
+    "article", "body", "content", "main", "post", "entry", "story", "text", "blog",
+];
+
+/// Paragraphs shorter than this do not count towards a candidate's score
+const MIN_PARAGRAPH_LEN: usize = 25;
+
+/// An extracted article
+#[derive(Debug, Clone)]
+pub struct Article {
+    /// Title of the page, if it has one
+    pub title: Option<String>,
+    /// The article content
+    pub text: RichText,
+}
+
+/// Extracts the main article from a page downloaded from `url`
+pub fn extract(html: &str, url: &str) -> Article {
+    let document = Html::parse_document(html);
+    let base = Url::parse(url).ok();
+
+    let root = best_candidate(&document).unwrap_or_else(|| document.root_element());
+    Article {
+        title: title(&document),
+        text: markup::from_element(root, base, is_unlikely),
+    }
+}
+
+/// Finds the page title in its metadata, falling back to the first heading
+fn title(document: &Html) -> Option<String> {
+    let select = |selector: &str| Selector::parse(selector).ok();
+    let og_title = select(r#"meta[property="og:title"]"#).and_then(|selector| {
+        document
+            .select(&selector)
+            .find_map(|meta| meta.value().attr("content").map(str::to_string))
+    });
+    let text_of = |selector: &str| {
+        let selector = select(selector)?;
+        let element = document.select(&selector).next()?;
+        Some(element.text().collect::<String>())
+    };
+    og_title
+        .or_else(|| text_of("title"))
+        .or_else(|| text_of("h1"))
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty())
+}
+
+/// Picks the element whose paragraphs make it most likely to be the article
+fn best_candidate(document: &Html) -> Option<ElementRef<'_>> {
+    let paragraphs = Selector::parse("p, pre, td").ok()?;
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+
+    for paragraph in document.select(&paragraphs) {
+        if paragraph
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(is_unlikely)
+        {
+            continue;
+        }
+        let text: String = paragraph.text().collect();
+        let len = text.trim().chars().count();
+        if len < MIN_PARAGRAPH_LEN {
+            continue;
+        }
+        let score = 1.0 + text.matches(',').count() as f64 + (len / 100).min(3) as f64;
+
+        // Parents get the full score and grandparents half of it
+        let ancestors = paragraph.ancestors().filter_map(ElementRef::wrap).take(2);
+        for (level, ancestor) in ancestors.enumerate() {
+            let entry = scores
+                .entry(ancestor.id())
+                .or_insert_with(|| initial_score(ancestor));
+            *entry += score / (level + 1) as f64;
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let element = ElementRef::wrap(document.tree.get(id)?)?;
+            Some((element, score * (1.0 - link_density(element))))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(element, _)| element)
+}
+
+/// Starting score of a candidate from its tag and its class and id
+fn initial_score(element: ElementRef) -> f64 {
+    let tag_score = match element.value().name() {
+        "article" => 10.0,
+        "div" | "main" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "form" | "ol" | "ul" | "dl" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    let names = class_and_id(element);
+    let mut class_score = 0.0;
+    if UNLIKELY.iter().any(|word| names.contains(word)) {
+        class_score -= 25.0;
+    }
+    if LIKELY.iter().any(|word| names.contains(word)) {
+        class_score += 25.0;
+    }
+    tag_score + class_score
+}
+
+/// Share of an element's text that sits inside links
+fn link_density(element: ElementRef) -> f64 {
+    let total: usize = element.text().map(|text| text.chars().count()).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let Ok(links) = Selector::parse("a") else {
+        return 0.0;
+    };
+    let linked: usize = element
+        .select(&links)
+        .flat_map(|link| link.text())
+        .map(|text| text.chars().count())
+        .sum();
+    linked as f64 / total as f64
+}
+
+/// Lowercased class and id of an element
+fn class_and_id(element: ElementRef) -> String {
+    let value = element.value();
+    format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.attr("id").unwrap_or_default()
+    )
+    .to_lowercase()
+}
+
+/// Whether an element is boilerplate rather than article content
+fn is_unlikely(element: ElementRef) -> bool {
+    let tag = element.value().name();
+    if SKIPPED_TAGS.contains(&tag) {
+        return true;
+    }
+    if matches!(tag, "html" | "body" | "article" | "main") {
+        return false;
+    }
+    let names = class_and_id(element);
+    UNLIKELY.iter().any(|word| names.contains(word))
+        && !LIKELY.iter().any(|word| names.contains(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Fallback title</title>
+  <meta property="og:title" content="  Why   Rust  ">
+  <script>var tracking = "Subscribe to our newsletter for more, every week";</script>
+  <style>body { font-family: serif; }</style>
+</head>
+<body>
+  <nav><a href="/">Home</a> <a href="/about">About</a> <p>Navigation that is long enough, with commas, to score</p></nav>
+  <header class="masthead"><p>The masthead of the site, repeated on every single page</p></header>
+  <div class="sidebar"><p>Related posts, popular posts, and other things to click on</p></div>
+  <article class="post">
+    <h1>Why Rust</h1>
+    <p>Rust is a systems language that is fast, safe, and pleasant to write.</p>
+    <p>Ownership rules out data races, use-after-free and double frees at compile time.</p>
+    <p>Read <a href="/more">the follow-up</a> for the details of the borrow checker.</p>
+  </article>
+  <div id="comments"><p>Great post, thanks, I learned a lot from reading this one!</p></div>
+  <footer><p>Copyright 2024, all rights reserved, do not copy this page</p></footer>
+  <script>console.log("loaded, tracking, analytics, everything");</script>
+</body>
+</html>"#;
+
+    #[test]
+    fn extracts_the_article_without_boilerplate() {
+        let article = extract(PAGE, "https://example.com/posts/why-rust");
+        assert_eq!(article.title.as_deref(), Some("Why Rust"));
+
+        let text = markup::to_plain(&article.text);
+        assert!(text.contains("Rust is a systems language"));
+        assert!(text.contains("Ownership rules out data races"));
+        for boilerplate in [
+            "Home",
+            "Navigation",
+            "masthead",
+            "Related posts",
+            "Great post",
+            "Copyright",
+            "tracking",
+            "font-family",
+        ] {
+            assert!(
+                !text.contains(boilerplate),
+                "{:?} in {:?}",
+                boilerplate,
+                text
+            );
+        }
+        // Relative links resolve against the page
+        assert_eq!(article.text.links, vec!["https://example.com/more"]);
+    }
+
+    #[test]
+    fn title_falls_back_to_the_title_element_and_first_heading() {
+        let title = |html: &str| extract(html, "https://example.com").title;
+        assert_eq!(
+            title("<title>\n  Page  title </title><h1>Heading</h1>").as_deref(),
+            Some("Page title")
+        );
+        assert_eq!(title("<h1>Heading</h1>").as_deref(), Some("Heading"));
+        assert_eq!(title("<p>No title here</p>"), None);
+    }
+}
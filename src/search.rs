@@ -0,0 +1,368 @@
+//! Full-text search through the HN Algolia API.
+//!
+//! Queries are typed as free text with optional inline filters:
+//!
+//! - `type:story`, `type:comment` or `type:all` (default `story`)
+//! - `points>N` for a score above N, or `points>=N` / `points:N` for a score of at least N
+//! - `since:24h`, `since:7d`, `since:2w`, `since:3m`, `since:1y` for a relative date range
+//! - `after:YYYY-MM-DD` / `before:YYYY-MM-DD` for an absolute date range
+//! - `sort:date` to order by recency instead of relevance
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Result};
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::{Item, ItemType};
+
+/// Default base URL of the search API
+pub const DEFAULT_SEARCH_URL: &str = "https://hn.algolia.com";
+
+/// Which kinds of items a search returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Stories,
+    Comments,
+    All,
+}
+
+/// A parsed search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Free-text part of the query
+    pub text: String,
+    /// Kinds of items to return
+    pub kind: SearchKind,
+    /// Minimum number of points
+    pub min_points: Option<u32>,
+    /// Only items created at or after this Unix timestamp
+    pub after: Option<u64>,
+    /// Only items created before this Unix timestamp
+    pub before: Option<u64>,
+    /// Order by date instead of relevance
+    pub by_date: bool,
+}
+
+impl SearchQuery {
+    /// Parses the text typed into the search prompt
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut query = SearchQuery {
+            text: String::new(),
+            kind: SearchKind::Stories,
+            min_points: None,
+            after: None,
+            before: None,
+            by_date: false,
+        };
+        let mut words = Vec::new();
+
+        for token in input.split_whitespace() {
+            if let Some(kind) = token.strip_prefix("type:") {
+                query.kind = match kind {
+                    "story" | "stories" => SearchKind::Stories,
+                    "comment" | "comments" => SearchKind::Comments,
+                    "all" => SearchKind::All,
+                    _ => bail!("Unknown type '{}' (use story, comment or all)", kind),
+                };
+            } else if let Some(points) = token
+                .strip_prefix("points>=")
+                .or_else(|| token.strip_prefix("points:"))
+            {
+                query.min_points = Some(parse_points(points, token)?);
+            } else if let Some(points) = token.strip_prefix("points>") {
+                query.min_points = Some(parse_points(points, token)?.saturating_add(1));
+            } else if let Some(range) = token.strip_prefix("since:") {
+                query.after = Some(now().saturating_sub(parse_duration(range)?));
+            } else if let Some(date) = token.strip_prefix("after:") {
+                query.after = Some(parse_date(date)?);
+            } else if let Some(date) = token.strip_prefix("before:") {
+                query.before = Some(parse_date(date)?);
+            } else if token == "sort:date" {
+                query.by_date = true;
+            } else {
+                words.push(token);
+            }
+        }
+
+        query.text = words.join(" ");
+        Ok(query)
+    }
+}
+
+/// One page of search results
+#[derive(Debug)]
+pub struct SearchPage {
+    /// Matching items converted to the regular item model
+    pub items: Vec<Item>,
+    /// Total number of result pages available
+    pub pages: usize,
+}
+
+/// A search hit as returned by the API
+#[derive(Deserialize, Debug)]
+struct Hit {
+    #[serde(rename = "objectID")]
+    object_id: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    points: Option<u32>,
+    #[serde(default)]
+    num_comments: Option<u32>,
+    #[serde(default)]
+    created_at_i: u64,
+    #[serde(default)]
+    story_text: Option<String>,
+    #[serde(default)]
+    comment_text: Option<String>,
+    #[serde(default)]
+    parent_id: Option<u64>,
+    #[serde(default, rename = "_tags")]
+    tags: Vec<String>,
+}
+
+/// Response body of the search endpoints
+#[derive(Deserialize, Debug)]
+struct Response {
+    hits: Vec<Hit>,
+    #[serde(default, rename = "nbPages")]
+    nb_pages: usize,
+}
+
+impl Hit {
+    /// Converts the hit into the regular item model
+    fn into_item(self) -> Option<Item> {
+        let has_tag = |tag: &str| self.tags.iter().any(|t| t == tag);
+        let kind = if has_tag("comment") {
+            ItemType::Comment
+        } else if has_tag("job") {
+            ItemType::Job
+        } else if has_tag("pollopt") {
+            ItemType::PollOpt
+        } else if has_tag("poll") {
+            ItemType::Poll
+        } else {
+            ItemType::Story
+        };
+
+        Some(Item {
+            id: self.object_id.parse().ok()?,
+            kind,
+            deleted: false,
+            dead: false,
+            title: self.title,
+            url: self.url,
+            score: self.points,
+            by: self.author,
+            time: self.created_at_i,
+            descendants: self.num_comments,
+            text: self.story_text.or(self.comment_text),
+            kids: Vec::new(),
+            parent: self.parent_id,
+            parts: Vec::new(),
+            poll: None,
+        })
+    }
+}
+
+/// Runs a search against the API at `base_url` and returns one page of results
+pub async fn search(
+    client: &Client,
+    base_url: &str,
+    query: &SearchQuery,
+    page: usize,
+    hits_per_page: usize,
+) -> Result<SearchPage> {
+    let endpoint = if query.by_date {
+        "search_by_date"
+    } else {
+        "search"
+    };
+    let url = format!("{}/api/v1/{}", base_url.trim_end_matches('/'), endpoint);
+
+    let tags = match query.kind {
+        SearchKind::Stories => "story",
+        SearchKind::Comments => "comment",
+        SearchKind::All => "(story,comment,poll,job)",
+    };
+    let mut numeric_filters = Vec::new();
+    if let Some(points) = query.min_points {
+        numeric_filters.push(format!("points>={}", points));
+    }
+    if let Some(after) = query.after {
+        numeric_filters.push(format!("created_at_i>={}", after));
+    }
+    if let Some(before) = query.before {
+        numeric_filters.push(format!("created_at_i<{}", before));
+    }
+
+    let mut params = vec![
+        ("query", query.text.clone()),
+        ("tags", tags.to_string()),
+        ("page", page.to_string()),
+        ("hitsPerPage", hits_per_page.to_string()),
+    ];
+    if !numeric_filters.is_empty() {
+        params.push(("numericFilters", numeric_filters.join(",")));
+    }
+
+    let response: Response = client
+        .get(&url)
+        .query(&params)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(SearchPage {
+        items: response
+            .hits
+            .into_iter()
+            .filter_map(Hit::into_item)
+            .collect(),
+        pages: response.nb_pages,
+    })
+}
+
+/// Current Unix time in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Parses the number of a `points` filter
+fn parse_points(value: &str, token: &str) -> Result<u32> {
+    match value.parse() {
+        Ok(points) => Ok(points),
+        Err(_) => bail!("Invalid points filter '{}'", token),
+    }
+}
+
+/// Parses a relative duration like `24h`, `7d`, `2w`, `3m` or `1y` into seconds
+fn parse_duration(value: &str) -> Result<u64> {
+    let invalid = || anyhow!("Invalid duration '{}' (try 24h, 7d or 1y)", value);
+    let unit_secs: u64 = match value.chars().last() {
+        Some('h') => 3600,
+        Some('d') => 86400,
+        Some('w') => 7 * 86400,
+        Some('m') => 30 * 86400,
+        Some('y') => 365 * 86400,
+        _ => return Err(invalid()),
+    };
+    // The unit is a single ASCII byte
+    let amount: u64 = value[..value.len() - 1].parse().map_err(|_| invalid())?;
+    amount.checked_mul(unit_secs).ok_or_else(invalid)
+}
+
+/// Parses a `YYYY-MM-DD` date into a Unix timestamp at midnight UTC
+fn parse_date(value: &str) -> Result<u64> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let parsed = match parts.as_slice() {
+        [y, m, d] => (y.parse::<i64>(), m.parse::<u32>(), d.parse::<u32>()),
+        _ => bail!("Invalid date '{}' (use YYYY-MM-DD)", value),
+    };
+    let (Ok(year @ 0..=9999), Ok(month @ 1..=12), Ok(day @ 1..=31)) = parsed else {
+        bail!("Invalid date '{}' (use YYYY-MM-DD)", value);
+    };
+
+    // Days since the Unix epoch for a proleptic Gregorian date
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    Ok((days.max(0) * 86400) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_searches_stories_by_relevance() {
+        let query = SearchQuery::parse("  rust   async ").unwrap();
+        assert_eq!(query.text, "rust async");
+        assert_eq!(query.kind, SearchKind::Stories);
+        assert_eq!(query.min_points, None);
+        assert_eq!((query.after, query.before), (None, None));
+        assert!(!query.by_date);
+    }
+
+    #[test]
+    fn type_filter() {
+        let kind = |input| SearchQuery::parse(input).unwrap().kind;
+        assert_eq!(kind("type:story"), SearchKind::Stories);
+        assert_eq!(kind("type:comments"), SearchKind::Comments);
+        assert_eq!(kind("type:all"), SearchKind::All);
+        assert!(SearchQuery::parse("type:poll").is_err());
+    }
+
+    #[test]
+    fn points_filter() {
+        let min_points = |input| SearchQuery::parse(input).unwrap().min_points;
+        assert_eq!(min_points("points>100"), Some(101));
+        assert_eq!(min_points("points>=100"), Some(100));
+        assert_eq!(min_points("points:100"), Some(100));
+        assert_eq!(min_points("points>4294967295"), Some(u32::MAX));
+        assert!(SearchQuery::parse("points>lots").is_err());
+        assert!(SearchQuery::parse("points>").is_err());
+        assert!(SearchQuery::parse("points>-1").is_err());
+    }
+
+    #[test]
+    fn since_filter() {
+        let query = SearchQuery::parse("since:7d sort:date").unwrap();
+        let after = query.after.unwrap();
+        assert!(now() - after >= 7 * 86400 && now() - after < 7 * 86400 + 60);
+        assert!(query.by_date);
+
+        assert_eq!(parse_duration("24h").unwrap(), 86400);
+        assert_eq!(parse_duration("2w").unwrap(), 14 * 86400);
+        assert_eq!(parse_duration("3m").unwrap(), 90 * 86400);
+        assert_eq!(parse_duration("1y").unwrap(), 365 * 86400);
+    }
+
+    #[test]
+    fn invalid_durations_are_errors() {
+        for input in ["", "d", "7", "7s", "7é", "é", "-1d", "99999999999999y"] {
+            let error = parse_duration(input).unwrap_err().to_string();
+            assert!(
+                error.starts_with("Invalid duration"),
+                "{}: {}",
+                input,
+                error
+            );
+        }
+        assert!(SearchQuery::parse("since:7é").is_err());
+        assert!(SearchQuery::parse("since:99999999999999y").is_err());
+    }
+
+    #[test]
+    fn date_filters() {
+        let query = SearchQuery::parse("after:2024-03-01 before:2024-04-01").unwrap();
+        assert_eq!(query.after, Some(1709251200));
+        assert_eq!(query.before, Some(1711929600));
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        for input in [
+            "2024-13-01",
+            "2024-00-10",
+            "2024-01-32",
+            "2024-01",
+            "yesterday",
+        ] {
+            assert!(parse_date(input).is_err(), "{}", input);
+        }
+        assert!(parse_date("99999999999999999-01-01").is_err());
+    }
+}